@@ -1,9 +1,17 @@
 use crate::{
-    gameplay::{fallback_puzzle, PlayingPuzzle, Puzzle},
-    generator::generate_puzzle,
+    deep_link::{self, DeepLink},
+    gameplay::{fallback_puzzle, GameGrid, PlayingPuzzle, Puzzle, PuzzleError, Replay},
+    generator::{
+        GenerationBudget, GenerationError, GenerationTask, GeneratorSettings, SolveTask,
+        SolverLimits,
+    },
+    puzzle_queue::PuzzleQueue,
+    save::{self, LoadedSave, SaveData, SaveDataRef},
     ux::{
-        edit_generator_settings, tutorial_window, update_game, GameState, GameStyle, PuzzleState,
-        SegmentMeshData, SettingsConfig,
+        edit_generator_settings, generation_error_window, generation_window, history_window,
+        jump_to_move, puzzle_code_window, replay_window, seed_window, solution_window,
+        tutorial_window, update_game, update_replay, GameState, GameStyle, PuzzleState, ReplayView,
+        ReplayViewAction, SegmentMeshData, SettingsConfig,
     },
 };
 
@@ -14,47 +22,123 @@ pub struct App {
     mesh_data: SegmentMeshData,
 
     config: SettingsConfig,
+    queue: PuzzleQueue,
 
     editing_generator_settings: bool,
     showing_tutorial: bool,
+    showing_seed: bool,
+    showing_history: bool,
+    seed_input: String,
+    loading_code: bool,
+    code_input: String,
+    code_error: Option<String>,
+    loading_replay: bool,
+    replay_input: String,
+    replay_error: Option<String>,
+    // Shown in place of the puzzle while watching a replay.
+    replay: Option<ReplayView>,
+    solution_search: Option<SolveTask>,
+    solution_error: Option<String>,
+    generation: Option<GenerationTask>,
+    // Waiting for the puzzle the queue is generating, rather than generating another.
+    awaiting_queue: bool,
+    generation_error: Option<GenerationError>,
+    // A save that could not be loaded, kept until it has been written to the backup key.
+    save_backup: Option<String>,
 }
 
-const PUZZLE_KEY: &str = "swap_puzzle";
-const PUZZLE_STATE_KEY: &str = "swap_puzzle_state";
-const SETTINGS_KEY: &str = "swap_settings";
-
 impl App {
     /// Called once before the first frame.
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let puzzle = cc
-            .storage
-            .and_then(|storage| eframe::get_value(storage, PUZZLE_KEY))
+        let LoadedSave { data, backup } = save::load(cc.storage);
+        let SaveData {
+            puzzle,
+            puzzle_state,
+            config,
+            mut queue,
+        } = data;
+
+        let puzzle = puzzle
+            .filter(|puzzle| match puzzle.puzzle().validate() {
+                Ok(()) if puzzle.is_consistent() => true,
+                Ok(()) => {
+                    log::warn!("Discarding saved puzzle with an inconsistent history");
+                    false
+                }
+                Err(errors) => {
+                    log::warn!(
+                        "Discarding saved puzzle: {}",
+                        PuzzleError::describe_all(&errors)
+                    );
+                    false
+                }
+            })
             .unwrap_or_else(|| PlayingPuzzle::play(fallback_puzzle()));
-        let puzzle_state = cc
-            .storage
-            .and_then(|storage| eframe::get_value(storage, PUZZLE_STATE_KEY))
-            .unwrap_or_default();
         let game_state = GameState::new(&puzzle);
+        queue.discard_invalid();
 
-        let config = cc
-            .storage
-            .and_then(|storage| eframe::get_value(storage, SETTINGS_KEY))
-            .unwrap_or_default();
-
-        Self {
+        let mut app = Self {
             puzzle,
             puzzle_state,
             game_state,
             mesh_data: SegmentMeshData::init(0.03, 0.02, 0.04),
             config,
+            queue,
             editing_generator_settings: false,
             showing_tutorial: false,
+            showing_seed: false,
+            showing_history: false,
+            seed_input: String::new(),
+            loading_code: false,
+            code_input: String::new(),
+            code_error: None,
+            loading_replay: false,
+            replay_input: String::new(),
+            replay_error: None,
+            replay: None,
+            solution_search: None,
+            solution_error: None,
+            generation: None,
+            awaiting_queue: false,
+            generation_error: None,
+            save_backup: backup,
+        };
+
+        // The page URL always links to the current puzzle, so a reload links to the saved
+        // one. Only a link to a different puzzle replaces it and its progress.
+        let current = app.puzzle.puzzle();
+        match deep_link::read_link(cc) {
+            Some(DeepLink::Puzzle(puzzle)) if puzzle == *current => {}
+            Some(DeepLink::Seed { seed, size })
+                if current.seed() == Some(seed)
+                    && size.map_or(true, |size| size == current.start().size()) => {}
+            Some(DeepLink::Puzzle(puzzle)) => app.set_puzzle(puzzle),
+            Some(DeepLink::Seed { seed, size }) => {
+                let mut settings = app.config.get_settings_for_seed(seed);
+                if let Some(size) = size {
+                    // Keep the configured size if the rest of the settings do not fit the
+                    // linked one.
+                    let linked = GeneratorSettings {
+                        size,
+                        ..settings.clone()
+                    };
+                    match linked.validate() {
+                        Ok(()) => settings = linked,
+                        Err(err) => log::warn!("Ignoring linked size: {err}"),
+                    }
+                }
+                app.start_generation(settings);
+            }
+            None => deep_link::link_to(app.puzzle.puzzle()),
         }
+        app
     }
 }
 
 impl App {
     fn set_puzzle_without_puzzle_state(&mut self, puzzle: Puzzle) {
+        self.solution_search = None;
+        self.awaiting_queue = false;
         self.puzzle = PlayingPuzzle::play(puzzle);
         self.game_state = GameState::new(&self.puzzle);
     }
@@ -64,21 +148,83 @@ impl App {
     }
 
     pub fn set_puzzle(&mut self, puzzle: Puzzle) {
+        deep_link::link_to(&puzzle);
         self.set_puzzle_without_puzzle_state(puzzle);
         self.puzzle_state = PuzzleState::default();
     }
+
+    fn next_puzzle(&mut self) {
+        if let Some(puzzle) = self.queue.pop(&self.config) {
+            self.generation = None;
+            self.set_puzzle(puzzle);
+        } else if self.generation.is_none() {
+            if self.queue.filling(&self.config).is_some() {
+                self.awaiting_queue = true;
+            } else {
+                self.start_generation(self.config.get_current_settings());
+            }
+        }
+    }
+
+    fn show_solution(&mut self) {
+        let puzzle = self.puzzle.puzzle();
+        self.solution_search = Some(SolveTask::start(
+            &GameGrid::from_puzzle_grid(puzzle.start()),
+            puzzle.swap_limit(),
+            SolverLimits::default(),
+        ));
+    }
+
+    fn start_generation(&mut self, settings: GeneratorSettings) {
+        self.awaiting_queue = false;
+        self.generation = Some(GenerationTask::start(settings, GenerationBudget::default()));
+    }
+
+    fn update_generation(&mut self, ctx: &egui::Context) {
+        if self.awaiting_queue {
+            if let Some(puzzle) = self.queue.pop(&self.config) {
+                self.set_puzzle(puzzle);
+            } else if let Some(attempts) = self.queue.filling(&self.config) {
+                if generation_window(ctx, attempts) {
+                    self.awaiting_queue = false;
+                }
+            } else {
+                // The queue gave up, or was dropped for new settings.
+                self.start_generation(self.config.get_current_settings());
+            }
+        }
+        if let Some(task) = &mut self.generation {
+            if let Some(result) = task.poll() {
+                self.generation = None;
+                match result {
+                    Ok(puzzle) => self.set_puzzle(puzzle),
+                    Err(err) => self.generation_error = Some(err),
+                }
+            } else if generation_window(ctx, task.attempts()) {
+                self.generation = None;
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
 }
 
 impl eframe::App for App {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, PUZZLE_KEY, &self.puzzle);
-        eframe::set_value(storage, PUZZLE_STATE_KEY, &self.puzzle_state);
-        eframe::set_value(storage, SETTINGS_KEY, &self.config);
+        let data = SaveDataRef {
+            puzzle: &self.puzzle,
+            puzzle_state: &self.puzzle_state,
+            config: &self.config,
+            queue: &self.queue,
+        };
+        save::write(storage, data, self.save_backup.take().as_deref());
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.queue.update(ctx, &self.config);
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 let is_web = cfg!(target_arch = "wasm32");
@@ -96,13 +242,71 @@ impl eframe::App for App {
                 if ui.button("Generator...").clicked() {
                     self.editing_generator_settings = true;
                 }
+                if ui.button("Seed...").clicked() {
+                    self.showing_seed = true;
+                }
+                if ui.button("History...").clicked() {
+                    self.showing_history = true;
+                }
+                if ui.button("Show solution").clicked() {
+                    self.show_solution();
+                }
+                ui.menu_button("Options", |ui| {
+                    ui.checkbox(&mut self.config.warn_when_stuck, "Warn me when stuck")
+                        .on_hover_text("Dim the swaps left once no solution fits in them");
+                    ui.checkbox(&mut self.config.check_button, "Check button")
+                        .on_hover_text("Add a button that marks the tiles breaking the rules");
+                });
+                ui.menu_button("Share", |ui| {
+                    if ui.button("Copy puzzle code").clicked() {
+                        let code = self.puzzle.puzzle().to_code();
+                        ui.output_mut(|output| output.copied_text = code);
+                        ui.close_menu();
+                    }
+                    if ui.button("Load puzzle code...").clicked() {
+                        self.loading_code = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Copy replay").clicked() {
+                        let replay = self.game_state.replay().to_string();
+                        ui.output_mut(|output| output.copied_text = replay);
+                        ui.close_menu();
+                    }
+                    if ui.button("Load replay...").clicked() {
+                        self.loading_replay = true;
+                        ui.close_menu();
+                    }
+                });
 
                 egui::widgets::global_dark_light_mode_buttons(ui);
+
+                if let Some(difficulty) = self.puzzle.puzzle().difficulty() {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.label(difficulty.name());
+                    });
+                }
             });
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.centered_and_justified(|ui| {
+                if let Some(view) = &mut self.replay {
+                    let action =
+                        update_replay(ui, view, &GameStyle { scale: 150.0 }, &self.mesh_data);
+                    if let Some(action) = action {
+                        self.replay = None;
+                        match action {
+                            ReplayViewAction::Close => {}
+                            ReplayViewAction::TryAgain => self.reset_puzzle(),
+                            ReplayViewAction::Next => self.next_puzzle(),
+                        }
+                    }
+                    return;
+                }
+                self.game_state
+                    .set_warn_when_stuck(self.config.warn_when_stuck);
+                self.game_state.set_show_check(self.config.check_button);
                 let response = update_game(
                     ui,
                     &mut self.puzzle,
@@ -113,11 +317,12 @@ impl eframe::App for App {
                 );
                 if let Some(response) = response {
                     match response {
-                        crate::ux::GameCompletionAction::Reset => self.reset_puzzle(),
-                        crate::ux::GameCompletionAction::Skip
-                        | crate::ux::GameCompletionAction::Solved => {
-                            self.set_puzzle(generate_puzzle(&self.config.get_current_settings()));
+                        crate::ux::GameCompletionAction::Reset => {
+                            self.generation = None;
+                            self.reset_puzzle();
                         }
+                        crate::ux::GameCompletionAction::Skip
+                        | crate::ux::GameCompletionAction::Solved => self.next_puzzle(),
                     }
                 }
             });
@@ -126,11 +331,60 @@ impl eframe::App for App {
                 ctx,
                 &mut self.config.custom_override,
                 &mut self.config.custom_settings,
+                &mut self.config.target_difficulty,
                 &mut self.editing_generator_settings,
             );
 
             tutorial_window(ctx, &mut self.showing_tutorial);
 
+            if let Some(target) = history_window(ctx, &self.puzzle, &mut self.showing_history) {
+                jump_to_move(
+                    target,
+                    &mut self.puzzle,
+                    &mut self.game_state,
+                    &mut self.puzzle_state,
+                );
+            }
+
+            if let Some(seed) = seed_window(
+                ctx,
+                self.puzzle.puzzle().seed(),
+                &mut self.seed_input,
+                &mut self.showing_seed,
+            ) {
+                self.start_generation(self.config.get_settings_for_seed(seed));
+            }
+
+            if let Some(puzzle) = puzzle_code_window(
+                ctx,
+                &mut self.code_input,
+                &mut self.code_error,
+                &mut self.loading_code,
+            ) {
+                self.generation = None;
+                self.set_puzzle(puzzle);
+            }
+
+            if let Some(replay) = replay_window(
+                ctx,
+                &mut self.replay_input,
+                &mut self.replay_error,
+                &mut self.loading_replay,
+            ) {
+                self.replay = Some(ReplayView::new(replay));
+            }
+
+            if let Some(solution) =
+                solution_window(ctx, &mut self.solution_search, &mut self.solution_error)
+            {
+                let replay = Replay::from_swaps(self.puzzle.puzzle().clone(), &solution, 1.0);
+                self.replay = Some(ReplayView::solution(replay));
+            }
+
+            self.update_generation(ctx);
+
+            generation_error_window(ctx, &mut self.generation_error);
+
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 powered_by_egui_and_eframe(ui);
                 egui::warn_if_debug_build(ui);
@@ -0,0 +1,382 @@
+//! Generates, solves and audits puzzles without opening the game.
+
+#![warn(clippy::all, rust_2018_idioms)]
+
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+
+use swap_wasm::{
+    gameplay::{
+        is_solved_by_regions, Cell, GameGrid, GridSolveState, Puzzle, PuzzleError, PuzzleTextError,
+        Replay, ReplayPlayer,
+    },
+    generator::{
+        find_solution, try_generate_puzzle_with_budget, GenerationBudget, GenerationJob,
+        GenerationReport, GeneratorSettings, SolverLimits,
+    },
+    grids::{Grid, GridSize},
+};
+use web_time::Instant;
+
+const USAGE: &str = "\
+usage:
+  swap_cli generate <settings.json> [--seed N] [--count N] [--out DIR] [--text]
+  swap_cli solve <puzzle.json|puzzle.txt>... [--max-swaps N]
+  swap_cli stats <settings.json> [--seed N] [--count N]
+  swap_cli replay <attempt.replay>...
+  swap_cli bench [--seed N] [--count N]
+
+Settings files hold a JSON generator settings object; missing fields use the defaults.
+Without --out, generated puzzles are written to stdout, one JSON object per line.
+With --text, puzzles use the plain-text format instead of JSON.";
+
+#[derive(Default)]
+struct Options {
+    paths: Vec<PathBuf>,
+    seed: Option<u64>,
+    count: Option<u64>,
+    out: Option<PathBuf>,
+    max_swaps: Option<u8>,
+    text: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    fn value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+        value
+            .ok_or_else(|| format!("{flag} needs a value"))?
+            .parse()
+            .map_err(|_| format!("invalid value for {flag}"))
+    }
+
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--text" => options.text = true,
+            "--seed" => options.seed = Some(value(arg, args.next())?),
+            "--count" => options.count = Some(value(arg, args.next())?),
+            "--out" => options.out = Some(value(arg, args.next())?),
+            "--max-swaps" => options.max_swaps = Some(value(arg, args.next())?),
+            flag if flag.starts_with("--") => return Err(format!("unknown option {flag}")),
+            path => options.paths.push(PathBuf::from(path)),
+        }
+    }
+    Ok(options)
+}
+
+fn read_settings(options: &Options) -> Result<GeneratorSettings, String> {
+    match options.paths.as_slice() {
+        [path] => {
+            let text = fs::read_to_string(path)
+                .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+            serde_json::from_str(&text)
+                .map_err(|err| format!("invalid settings in {}: {err}", path.display()))
+        }
+        _ => Err("expected exactly one settings file".to_owned()),
+    }
+}
+
+/// Seeds for each puzzle in a batch, so a batch can be reproduced from its first seed.
+fn batch_seeds(options: &Options) -> impl Iterator<Item = u64> {
+    let first = options.seed.unwrap_or_else(rand::random);
+    (0..options.count.unwrap_or(1)).map(move |i| first.wrapping_add(i))
+}
+
+fn generate(options: &Options) -> Result<(), String> {
+    let mut settings = read_settings(options)?;
+    if let Some(out) = &options.out {
+        fs::create_dir_all(out)
+            .map_err(|err| format!("could not create {}: {err}", out.display()))?;
+    }
+
+    let mut failures = 0;
+    for seed in batch_seeds(options) {
+        settings.seed = Some(seed);
+        let puzzle = match try_generate_puzzle_with_budget(&settings, GenerationBudget::default()) {
+            Ok(puzzle) => puzzle,
+            Err(err) => {
+                eprintln!("seed {seed}: {err}");
+                failures += 1;
+                continue;
+            }
+        };
+        match &options.out {
+            Some(out) => {
+                let (path, contents) = if options.text {
+                    (out.join(format!("puzzle-{seed}.txt")), puzzle.to_string())
+                } else {
+                    let json =
+                        serde_json::to_string_pretty(&puzzle).map_err(|err| err.to_string())?;
+                    (out.join(format!("puzzle-{seed}.json")), json)
+                };
+                fs::write(&path, contents)
+                    .map_err(|err| format!("could not write {}: {err}", path.display()))?;
+            }
+            None if options.text => println!("{puzzle}"),
+            None => {
+                println!(
+                    "{}",
+                    serde_json::to_string(&puzzle).map_err(|err| err.to_string())?
+                );
+            }
+        }
+    }
+
+    if failures > 0 {
+        Err(format!("{failures} puzzles could not be generated"))
+    } else {
+        Ok(())
+    }
+}
+
+fn read_puzzle(path: &Path) -> Result<Puzzle, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+    let puzzle = if path.extension().is_some_and(|ext| ext == "txt") {
+        text.parse().map_err(|err: PuzzleTextError| err.to_string())
+    } else {
+        serde_json::from_str(&text).map_err(|err| err.to_string())
+    };
+    let puzzle = puzzle.and_then(|puzzle: Puzzle| {
+        puzzle
+            .validate()
+            .map_err(|errors| PuzzleError::describe_all(&errors))?;
+        Ok(puzzle)
+    });
+    puzzle.map_err(|err| format!("invalid puzzle in {}: {err}", path.display()))
+}
+
+fn solve(options: &Options) -> Result<(), String> {
+    if options.paths.is_empty() {
+        return Err("expected at least one puzzle file".to_owned());
+    }
+    for path in options.paths.iter() {
+        let puzzle = read_puzzle(path)?;
+        let max_swaps = options.max_swaps.unwrap_or(puzzle.swap_limit());
+        match find_solution(&puzzle, max_swaps, SolverLimits::default()) {
+            Ok(Some(solution)) => {
+                println!("{}: {} swaps", path.display(), solution.len());
+                for record in solution.iter() {
+                    println!("  {record}");
+                }
+            }
+            Ok(None) => println!("{}: no solution in {max_swaps} swaps", path.display()),
+            Err(err) => println!("{}: {err}", path.display()),
+        }
+    }
+    Ok(())
+}
+
+/// Plays each replay through and reports how the attempt ended.
+fn replay(options: &Options) -> Result<(), String> {
+    if options.paths.is_empty() {
+        return Err("expected at least one replay file".to_owned());
+    }
+    for path in options.paths.iter() {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+        let replay: Replay = text
+            .parse()
+            .map_err(|err| format!("invalid replay in {}: {err}", path.display()))?;
+        if let Err(err) = replay.validate() {
+            println!("{}: {err}", path.display());
+            continue;
+        }
+        let mut player = ReplayPlayer::new(replay);
+        while !player.is_finished() {
+            player.step().map_err(|err| err.to_string())?;
+        }
+        let puzzle = player.puzzle();
+        println!(
+            "{}: {} events over {:.1}s, {:?} after {} of {} swaps",
+            path.display(),
+            player.replay().events().len(),
+            player.replay().duration(),
+            puzzle.is_solved(),
+            puzzle.swaps_made(),
+            puzzle.swap_limit()
+        );
+    }
+    Ok(())
+}
+
+fn stats(options: &Options) -> Result<(), String> {
+    let mut settings = read_settings(options)?;
+    let mut total = GenerationReport::default();
+    let mut generated = 0;
+    let start = Instant::now();
+    for seed in batch_seeds(options) {
+        settings.seed = Some(seed);
+        let puzzle_start = Instant::now();
+        let mut job = GenerationJob::new(settings.clone(), GenerationBudget::default())
+            .map_err(|err| err.to_string())?;
+        let result = loop {
+            if let Some(result) = job.step() {
+                break result;
+            }
+        };
+        let outcome = match result {
+            Ok(puzzle) => {
+                generated += 1;
+                match puzzle.difficulty() {
+                    Some(difficulty) => format!("ok, {}", difficulty.name()),
+                    None => "ok".to_owned(),
+                }
+            }
+            Err(err) => err.to_string(),
+        };
+        println!(
+            "seed {seed}: {outcome} ({}) in {:.1?}",
+            job.report(),
+            puzzle_start.elapsed()
+        );
+        total += *job.report();
+    }
+    println!(
+        "generated {generated} puzzles in {:.1?}: {total}",
+        start.elapsed()
+    );
+    Ok(())
+}
+
+/// Times the solver on generated puzzles it can finish, and compares the two ways of
+/// checking a searched position on every position one swap from their starts: a fresh
+/// [`Connectivity`], as the solver does, against refilling the swapped regions and then
+/// walking every region.
+/// The solver as it was before the union-find connectivity: every queued position keeps
+/// its grid, which is refilled after each swap and checked by walking its regions. Returns
+/// the length of a shortest solution, or `Err` once it has tried `max_nodes` positions.
+fn find_solution_by_regions(puzzle: &Puzzle, max_nodes: usize) -> Result<Option<usize>, ()> {
+    let key = |grid: &Grid<Cell>| -> Vec<u16> {
+        grid.size()
+            .into_iter()
+            .map(|index| grid.get(index).map(Cell::layout_key).unwrap_or(0))
+            .collect()
+    };
+    let start: Grid<Cell> = Grid::from_puzzle_grid(puzzle.start());
+    if is_solved_by_regions(&start) == GridSolveState::Solved {
+        return Ok(Some(0));
+    }
+    let mut visited = HashSet::from([key(&start)]);
+    let mut frontier = VecDeque::from([(start, 0)]);
+    let mut nodes = 0;
+    while let Some((grid, depth)) = frontier.pop_front() {
+        if depth >= puzzle.swap_limit() as usize {
+            continue;
+        }
+        let indices: Vec<_> = grid.indicies().collect();
+        for (i, &a) in indices.iter().enumerate() {
+            for &b in &indices[i + 1..] {
+                if nodes >= max_nodes {
+                    return Err(());
+                }
+                let mut next = grid.clone();
+                if next.swap_with_rotation(a, b).is_none() || !visited.insert(key(&next)) {
+                    continue;
+                }
+                nodes += 1;
+                next.fill_after_swap(a, b);
+                if is_solved_by_regions(&next) == GridSolveState::Solved {
+                    return Ok(Some(depth + 1));
+                }
+                frontier.push_back((next, depth + 1));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn bench(options: &Options) -> Result<(), String> {
+    const MAX_NODES: usize = 1_000_000;
+    const LIMITS: SolverLimits = SolverLimits {
+        max_nodes: Some(MAX_NODES),
+        max_duration: None,
+    };
+
+    let options = Options {
+        seed: Some(options.seed.unwrap_or(0)),
+        count: Some(options.count.unwrap_or(5)),
+        ..Options::default()
+    };
+    for (size, swap_count) in [(5, 3), (6, 2)] {
+        let mut settings = GeneratorSettings {
+            size: GridSize::new(size, size),
+            swap_count,
+            ..GeneratorSettings::default()
+        };
+        let mut puzzles = Vec::new();
+        for seed in batch_seeds(&options) {
+            settings.seed = Some(seed);
+            match try_generate_puzzle_with_budget(&settings, GenerationBudget::default()) {
+                Ok(puzzle) => puzzles.push(puzzle),
+                Err(err) => eprintln!("{size}x{size} seed {seed}: {err}"),
+            }
+        }
+
+        let mut by_connectivity = Duration::ZERO;
+        let mut by_regions = Duration::ZERO;
+        let (mut solved, mut gave_up) = (0, 0);
+        for puzzle in puzzles.iter() {
+            let start = Instant::now();
+            let new = find_solution(puzzle, puzzle.swap_limit(), LIMITS);
+            by_connectivity += start.elapsed();
+
+            let start = Instant::now();
+            let old = find_solution_by_regions(puzzle, MAX_NODES);
+            by_regions += start.elapsed();
+
+            match (new, old) {
+                (Ok(new), Ok(old)) if new.as_ref().map(Vec::len) == old => {
+                    solved += usize::from(new.is_some());
+                }
+                (Ok(_), Ok(_)) => {
+                    return Err(format!(
+                        "the two solvers disagree on a {size}x{size} puzzle"
+                    ))
+                }
+                _ => gave_up += 1,
+            }
+        }
+        let count = puzzles.len().max(1) as u32;
+        println!(
+            "{size}x{size}, {swap_count} swaps, {} puzzles ({solved} solved, {gave_up} gave up):",
+            puzzles.len()
+        );
+        println!(
+            "  find_solution {:.1?} each, before union-find {:.1?} each ({:.1}x)",
+            by_connectivity / count,
+            by_regions / count,
+            by_regions.as_secs_f64() / by_connectivity.as_secs_f64().max(f64::EPSILON)
+        );
+    }
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (command, rest) = args.split_first().ok_or("expected a command")?;
+    let options = parse_options(rest)?;
+    match command.as_str() {
+        "generate" => generate(&options),
+        "solve" => solve(&options),
+        "stats" => stats(&options),
+        "replay" => replay(&options),
+        "bench" => bench(&options),
+        _ => Err(format!("unknown command {command}")),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
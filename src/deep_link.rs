@@ -0,0 +1,193 @@
+//! Links to a puzzle through the page URL fragment, e.g. `#puzzle=<code>` or
+//! `#seed=1234&size=4x4`. Only the web build reads and writes the page location.
+
+use crate::{
+    gameplay::{Puzzle, PuzzleError, MAX_PUZZLE_SIDE},
+    grids::GridSize,
+};
+
+pub enum DeepLink {
+    Puzzle(Puzzle),
+    Seed { seed: u64, size: Option<GridSize> },
+}
+
+fn parse_size(text: &str) -> Option<GridSize> {
+    let (width, height) = text.split_once('x')?;
+    let side = |text: &str| {
+        text.parse()
+            .ok()
+            .filter(|side| (1..=MAX_PUZZLE_SIDE).contains(side))
+    };
+    Some(GridSize::new(side(width)?, side(height)?))
+}
+
+/// Parses a URL fragment, with or without the leading `#`. Malformed links are logged
+/// and ignored.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub fn parse_fragment(fragment: &str) -> Option<DeepLink> {
+    let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+    let mut puzzle = None;
+    let mut seed = None;
+    let mut size = None;
+    for pair in fragment.split('&').filter(|pair| !pair.is_empty()) {
+        match pair.split_once('=') {
+            Some(("puzzle", code)) => match Puzzle::from_code(code) {
+                Ok(linked) => match linked.validate() {
+                    Ok(()) => puzzle = Some(linked),
+                    Err(errors) => log::warn!(
+                        "Ignoring linked puzzle: {}",
+                        PuzzleError::describe_all(&errors)
+                    ),
+                },
+                Err(err) => log::warn!("Ignoring linked puzzle: {err}"),
+            },
+            Some(("seed", value)) => match value.parse() {
+                Ok(value) => seed = Some(value),
+                Err(_) => log::warn!("Ignoring linked seed {value:?}"),
+            },
+            Some(("size", value)) => match parse_size(value) {
+                Some(value) => size = Some(value),
+                None => log::warn!("Ignoring linked size {value:?}"),
+            },
+            _ => log::warn!("Ignoring unknown link parameter {pair:?}"),
+        }
+    }
+    match (puzzle, seed) {
+        (Some(puzzle), _) => Some(DeepLink::Puzzle(puzzle)),
+        (None, Some(seed)) => Some(DeepLink::Seed { seed, size }),
+        (None, None) => None,
+    }
+}
+
+/// The fragment that links to `puzzle`, without the leading `#`.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub fn fragment_for(puzzle: &Puzzle) -> String {
+    format!("puzzle={}", puzzle.to_code())
+}
+
+/// Reads the link the page was opened with.
+#[cfg(target_arch = "wasm32")]
+pub fn read_link(cc: &eframe::CreationContext<'_>) -> Option<DeepLink> {
+    parse_fragment(&cc.integration_info.web_info.location.hash)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_link(_cc: &eframe::CreationContext<'_>) -> Option<DeepLink> {
+    None
+}
+
+/// Points the page URL at `puzzle` without adding a browser history entry.
+#[cfg(target_arch = "wasm32")]
+pub fn link_to(puzzle: &Puzzle) {
+    if let Some(window) = web_sys::window() {
+        let fragment = format!("#{}", fragment_for(puzzle));
+        if window.location().replace(&fragment).is_err() {
+            log::warn!("Could not update the page URL");
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn link_to(_puzzle: &Puzzle) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn puzzle() -> Puzzle {
+        "swaps: 1\nhint: 0 0\ngrid:\nE@p W E@p W\n".parse().unwrap()
+    }
+
+    #[test]
+    fn puzzle_links_parse() {
+        let code = puzzle().to_code();
+        for fragment in [format!("#puzzle={code}"), format!("puzzle={code}")] {
+            assert!(
+                matches!(parse_fragment(&fragment), Some(DeepLink::Puzzle(linked)) if linked == puzzle()),
+                "{fragment}"
+            );
+        }
+        // A puzzle takes precedence over a seed.
+        let fragment = format!("#seed=5&puzzle={code}");
+        assert!(matches!(
+            parse_fragment(&fragment),
+            Some(DeepLink::Puzzle(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_puzzles_are_ignored() {
+        let code = puzzle().to_code();
+        assert!(parse_fragment("#puzzle=").is_none());
+        assert!(parse_fragment("#puzzle=not-a-code").is_none());
+        assert!(parse_fragment(&format!("#puzzle={}", &code[..code.len() - 2])).is_none());
+        // A valid code for a puzzle that breaks the rules.
+        let invalid = crate::gameplay::debug_puzzle::debug_puzzle().to_code();
+        assert!(parse_fragment(&format!("#puzzle={invalid}")).is_none());
+        // The seed is still used when the puzzle is malformed.
+        assert!(matches!(
+            parse_fragment("#puzzle=bad&seed=7"),
+            Some(DeepLink::Seed {
+                seed: 7,
+                size: None
+            })
+        ));
+    }
+
+    #[test]
+    fn seed_links_parse() {
+        assert!(matches!(
+            parse_fragment("#seed=1234"),
+            Some(DeepLink::Seed {
+                seed: 1234,
+                size: None
+            })
+        ));
+        assert!(matches!(
+            parse_fragment("seed=1234&size=4x5"),
+            Some(DeepLink::Seed { seed: 1234, size: Some(size) }) if size == GridSize::new(4, 5)
+        ));
+        assert!(matches!(
+            parse_fragment("#size=4x5&&seed=0&"),
+            Some(DeepLink::Seed {
+                seed: 0,
+                size: Some(_)
+            })
+        ));
+    }
+
+    #[test]
+    fn malformed_seeds_and_sizes_are_ignored() {
+        for fragment in [
+            "",
+            "#",
+            "#seed=",
+            "#seed=-1",
+            "#seed=abc",
+            "#size=4x4",
+            "#seed",
+        ] {
+            assert!(parse_fragment(fragment).is_none(), "{fragment}");
+        }
+        let too_wide = format!("#seed=1&size={}x4", MAX_PUZZLE_SIDE + 1);
+        for fragment in [
+            "#seed=1&size=4",
+            "#seed=1&size=0x4",
+            "#seed=1&size=4x",
+            "#seed=1&size=axb",
+            "#seed=1&unknown=2",
+            too_wide.as_str(),
+        ] {
+            assert!(
+                matches!(
+                    parse_fragment(fragment),
+                    Some(DeepLink::Seed {
+                        seed: 1,
+                        size: None
+                    })
+                ),
+                "{fragment}"
+            );
+        }
+    }
+}
@@ -87,6 +87,24 @@ impl Cell {
         }
     }
 
+    /// Compact encoding of the cell's connections and source, ignoring its fill.
+    pub fn layout_key(&self) -> u16 {
+        const PRESENT: u16 = 1 << 15;
+        const INTERSECTION: u16 = 1 << 14;
+        match self.data {
+            CellData::Normal { layer, source } => {
+                let source = source.map(|color| color.bit() as u16 + 1).unwrap_or(0);
+                PRESENT | layer.connections.bits() as u16 | source << 8
+            }
+            CellData::Intersection { layers } => {
+                PRESENT
+                    | INTERSECTION
+                    | layers[0].connections.bits() as u16
+                    | (layers[1].connections.bits() as u16) << 4
+            }
+        }
+    }
+
     pub fn total_connections(&self) -> usize {
         match self.data {
             CellData::Normal { layer, source: _ } => layer.connections.len(),
@@ -140,15 +158,28 @@ impl Cell {
         }
     }
 
-    pub fn iter_layers(&self) -> impl Iterator<Item = &CellLayer> {
+    pub fn layers(&self) -> &[CellLayer] {
         match &self.data {
             CellData::Normal {
                 layer,
                 source: _source,
-            } => vec![layer],
-            CellData::Intersection { layers } => layers.iter().collect(),
+            } => std::slice::from_ref(layer),
+            CellData::Intersection { layers } => layers,
+        }
+    }
+
+    pub fn layers_mut(&mut self) -> &mut [CellLayer] {
+        match &mut self.data {
+            CellData::Normal {
+                layer,
+                source: _source,
+            } => std::slice::from_mut(layer),
+            CellData::Intersection { layers } => layers,
         }
-        .into_iter()
+    }
+
+    pub fn iter_layers(&self) -> impl Iterator<Item = &CellLayer> {
+        self.layers().iter()
     }
 
     pub fn get_layer_for_direction(&self, direction: Direction) -> Option<(usize, &CellLayer)> {
@@ -0,0 +1,140 @@
+use crate::grids::{Direction, Grid};
+
+use super::{
+    game_grid::{GridSolveState, LayerSlots},
+    Cell, ColorSet, GameGridIndex,
+};
+
+/// Which layers of a grid are joined by their connections, found in one pass over the
+/// grid with a disjoint set.
+///
+/// The region of each layer and the colors of the sources in it are enough both to fill
+/// the grid and to tell whether it is solved.
+#[derive(Debug, Clone)]
+pub struct Connectivity {
+    slots: LayerSlots,
+    // Indexed by layer slot. After construction every layer points at the root of
+    // its region, and slots without a layer hold `NO_LAYER`.
+    roots: Vec<usize>,
+    // The source colors of each region, by root slot.
+    colors: Vec<ColorSet>,
+    open_ends: usize,
+}
+
+impl Connectivity {
+    const NO_LAYER: usize = usize::MAX;
+
+    pub fn new(grid: &Grid<Cell>) -> Self {
+        let slots = LayerSlots::new(grid.size());
+        let slot_count = slots.count();
+        let mut connectivity = Self {
+            slots,
+            roots: vec![Self::NO_LAYER; slot_count],
+            colors: vec![ColorSet::empty(); slot_count],
+            open_ends: 0,
+        };
+
+        // Each matched pair of connections is found from its west or north end, and
+        // every other connection is an open end.
+        let mut connections = 0;
+        let mut matched = 0;
+        for (index, cell) in grid.iter() {
+            for (layer_index, layer) in cell.iter_layers().enumerate() {
+                let slot = connectivity.slots.slot(index, layer_index);
+                if connectivity.roots[slot] == Self::NO_LAYER {
+                    connectivity.roots[slot] = slot;
+                }
+                connections += layer.connections.len();
+                for direction in [Direction::E, Direction::S] {
+                    if !layer.connections.contains(direction) {
+                        continue;
+                    }
+                    let neighbor = grid.get_neighbor(index, direction).and_then(|(at, cell)| {
+                        cell.get_layer_for_direction(direction.inverse())
+                            .map(|(layer_index, _)| connectivity.slots.slot(at, layer_index))
+                    });
+                    if let Some(other) = neighbor {
+                        if connectivity.roots[other] == Self::NO_LAYER {
+                            connectivity.roots[other] = other;
+                        }
+                        connectivity.union(slot, other);
+                        matched += 1;
+                    }
+                }
+            }
+            if let Some(source) = cell.source() {
+                let slot = connectivity.slots.slot(index, 0);
+                connectivity.colors[slot].insert(source);
+            }
+        }
+        connectivity.open_ends = connections - 2 * matched;
+
+        for slot in 0..slot_count {
+            if connectivity.roots[slot] == Self::NO_LAYER {
+                continue;
+            }
+            let root = connectivity.find(slot);
+            connectivity.roots[slot] = root;
+            if root != slot {
+                let colors = connectivity.colors[slot];
+                connectivity.colors[root] = connectivity.colors[root].union(colors);
+            }
+        }
+        connectivity
+    }
+
+    fn find(&mut self, mut slot: usize) -> usize {
+        while self.roots[slot] != slot {
+            self.roots[slot] = self.roots[self.roots[slot]];
+            slot = self.roots[slot];
+        }
+        slot
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        self.roots[a.max(b)] = a.min(b);
+    }
+
+    /// The colors of the sources in the region of `index`.
+    pub fn fill(&self, index: GameGridIndex) -> ColorSet {
+        self.colors[self.roots[self.slots.of(index)]]
+    }
+
+    pub fn same_region(&self, a: GameGridIndex, b: GameGridIndex) -> bool {
+        self.roots[self.slots.of(a)] == self.roots[self.slots.of(b)]
+    }
+
+    /// How many connections do not meet one on the neighboring tile.
+    pub const fn open_ends(&self) -> usize {
+        self.open_ends
+    }
+
+    /// The first rule the grid breaks, checked in the order the rules build on each other.
+    pub fn state(&self) -> GridSolveState {
+        if self.open_ends > 0 {
+            return GridSolveState::NotAllConnected;
+        }
+        let regions = || {
+            (0..self.roots.len())
+                .filter(|&slot| self.roots[slot] == slot)
+                .map(|slot| self.colors[slot])
+        };
+        if regions().any(|colors| colors.is_empty()) {
+            return GridSolveState::NotAllFilled;
+        }
+        if regions().any(|colors| colors.iter().nth(1).is_some()) {
+            return GridSolveState::DoubleFilled;
+        }
+        // Each region now has a single color, which no other region may share.
+        let mut seen = ColorSet::empty();
+        for colors in regions() {
+            if seen.union(colors) == seen {
+                return GridSolveState::DuplicateColorSection;
+            }
+            seen = seen.union(colors);
+        }
+        GridSolveState::Solved
+    }
+}
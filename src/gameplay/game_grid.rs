@@ -1,10 +1,8 @@
-use std::collections::VecDeque;
+use egui::ahash::{HashSet, HashSetExt};
 
-use egui::ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
+use crate::grids::{Direction, Grid, GridIndex, GridSize, Rotation};
 
-use crate::grids::{Grid, GridIndex, Rotation};
-
-use super::{cell::CellLayer, Cell, ColorSet, PuzzleCell, SwapRecord};
+use super::{cell::CellLayer, Cell, Color, ColorSet, Connectivity, PuzzleCell, SwapRecord};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde:: Deserialize)]
 pub enum GridSolveState {
@@ -21,14 +19,132 @@ pub struct GameGridIndex {
     layer_index: usize,
 }
 
+impl GameGridIndex {
+    pub const fn grid_index(&self) -> GridIndex {
+        self.grid_index
+    }
+
+    pub const fn layer_index(&self) -> usize {
+        self.layer_index
+    }
+}
+
+/// Numbers every layer a grid could have, so that tables with an entry per layer can be
+/// plain vectors.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LayerSlots {
+    width: usize,
+    count: usize,
+}
+
+impl LayerSlots {
+    // Tiles have at most two layers.
+    const PER_TILE: usize = 2;
+
+    pub fn new(size: GridSize) -> Self {
+        Self {
+            width: size.width,
+            count: size.width * size.height * Self::PER_TILE,
+        }
+    }
+
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+
+    pub const fn slot(&self, grid_index: GridIndex, layer_index: usize) -> usize {
+        (grid_index.y * self.width + grid_index.x) * Self::PER_TILE + layer_index
+    }
+
+    pub const fn of(&self, index: GameGridIndex) -> usize {
+        self.slot(index.grid_index, index.layer_index)
+    }
+}
+
+/// Layers joined to each other by their connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// In grid order.
+    pub layers: Vec<GameGridIndex>,
+    /// The source tiles in the region, in grid order.
+    pub sources: Vec<(GridIndex, Color)>,
+    /// The colors of the sources, which is what the region is filled with.
+    pub fill: ColorSet,
+    /// Connections that do not meet one on the neighboring tile.
+    pub open_ends: Vec<(GameGridIndex, Direction)>,
+}
+
+/// Every reason a grid is not solved, by layer. Lists are in grid order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolveReport {
+    /// Layers with a connection that does not meet one on the neighboring tile.
+    pub open_ends: Vec<GameGridIndex>,
+    pub unfilled: Vec<GameGridIndex>,
+    /// Layers reached by more than one color.
+    pub double_filled: Vec<GameGridIndex>,
+    /// Connected regions holding sources of a color whose sources are not all joined up.
+    pub split_regions: Vec<SplitRegion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitRegion {
+    pub color: Color,
+    pub layers: Vec<GameGridIndex>,
+}
+
+impl SolveReport {
+    pub fn is_solved(&self) -> bool {
+        self.state() == GridSolveState::Solved
+    }
+
+    /// The first problem found, in the same order as [`GameGrid::is_solved`].
+    pub fn state(&self) -> GridSolveState {
+        if !self.open_ends.is_empty() {
+            GridSolveState::NotAllConnected
+        } else if !self.unfilled.is_empty() {
+            GridSolveState::NotAllFilled
+        } else if !self.double_filled.is_empty() {
+            GridSolveState::DoubleFilled
+        } else if !self.split_regions.is_empty() {
+            GridSolveState::DuplicateColorSection
+        } else {
+            GridSolveState::Solved
+        }
+    }
+
+    /// Every layer with a problem, each listed once.
+    pub fn layers(&self) -> impl Iterator<Item = GameGridIndex> + '_ {
+        let mut seen = HashSet::new();
+        self.open_ends
+            .iter()
+            .chain(self.unfilled.iter())
+            .chain(self.double_filled.iter())
+            .chain(
+                self.split_regions
+                    .iter()
+                    .flat_map(|region| region.layers.iter()),
+            )
+            .copied()
+            .filter(move |index| seen.insert(*index))
+    }
+}
+
 pub trait GameGrid {
     fn from_puzzle_grid(puzzle_grid: Grid<PuzzleCell>) -> Self;
 
     fn is_solved(&self) -> GridSolveState;
+    /// Like [`GameGrid::is_solved`], but lists every problem rather than the first.
+    fn solve_report(&self) -> SolveReport;
 
     fn swap_with_rotation(&mut self, a: GridIndex, b: GridIndex) -> Option<SwapRecord>;
     fn undo_swap(&mut self, record: SwapRecord);
     fn fill(&mut self);
+    /// Fills every layer from the connectivity of this grid, e.g. one also used to check
+    /// whether it is solved.
+    fn fill_from(&mut self, connectivity: &Connectivity);
+    /// Updates the fill after the tiles at `a` and `b` were swapped or rotated, recomputing
+    /// only the regions that could have changed. The grid must have been filled before.
+    fn fill_after_swap(&mut self, a: GridIndex, b: GridIndex);
 
     fn get_layer(&self, index: GameGridIndex) -> Option<&CellLayer>;
     fn get_layer_mut(&mut self, index: GameGridIndex) -> Option<&mut CellLayer>;
@@ -40,6 +156,9 @@ pub trait GameGrid {
         index: GameGridIndex,
     ) -> Option<impl Iterator<Item = (GameGridIndex, &CellLayer)>>;
     fn all_connected(&self, index: GameGridIndex) -> Option<HashSet<GameGridIndex>>;
+    /// Splits the layers into regions. Every layer is in exactly one region, and regions
+    /// are in grid order of their first layer.
+    fn regions(&self) -> Vec<Region>;
 }
 
 fn can_swap(grid: &Grid<Cell>, a: GridIndex, b: GridIndex) -> bool {
@@ -54,6 +173,43 @@ fn can_swap(grid: &Grid<Cell>, a: GridIndex, b: GridIndex) -> bool {
     false
 }
 
+/// The check [`GameGrid::is_solved`] made before [`Connectivity`]: from the fill, walking
+/// every layer's neighbors and then each region. It is slower, and kept to test and
+/// benchmark against. The grid must be filled.
+pub fn is_solved_by_regions(grid: &Grid<Cell>) -> GridSolveState {
+    let all_connected = grid.iter_layers().all(|(index, layer)| {
+        grid.iter_connected_layers(index).unwrap().count() == layer.connections.len()
+    });
+    if !all_connected {
+        return GridSolveState::NotAllConnected;
+    }
+
+    let all_filled = grid
+        .iter_layers()
+        .all(|(_index, layer)| !layer.fill.is_empty());
+    if !all_filled {
+        return GridSolveState::NotAllFilled;
+    }
+
+    let double_filled = grid
+        .iter_layers()
+        .any(|(_index, layer)| layer.fill.iter().take(2).count() == 2);
+    if double_filled {
+        return GridSolveState::DoubleFilled;
+    }
+
+    // A color is split when its sources are in more than one region.
+    let mut seen = ColorSet::empty();
+    for region in grid.regions() {
+        if region.fill.iter().any(|color| seen.contains(color)) {
+            return GridSolveState::DuplicateColorSection;
+        }
+        seen = seen.union(region.fill);
+    }
+
+    GridSolveState::Solved
+}
+
 impl GameGrid for Grid<Cell> {
     fn from_puzzle_grid(puzzle_grid: Grid<PuzzleCell>) -> Self {
         let mut grid = Self::with_size(puzzle_grid.size());
@@ -65,68 +221,38 @@ impl GameGrid for Grid<Cell> {
     }
 
     fn is_solved(&self) -> GridSolveState {
-        let all_connected = self.iter_layers().all(|(index, layer)| {
-            self.iter_connected_layers(index).unwrap().count() == layer.connections.len()
-        });
-        if !all_connected {
-            return GridSolveState::NotAllConnected;
-        }
-
-        let all_filled = self
-            .iter_layers()
-            .all(|(_index, layer)| !layer.fill.is_empty());
-        if !all_filled {
-            return GridSolveState::NotAllFilled;
-        }
+        Connectivity::new(self).state()
+    }
 
-        let double_filled = self
-            .iter_layers()
-            .any(|(_index, layer)| layer.fill.iter().take(2).count() == 2);
-        if double_filled {
-            return GridSolveState::DoubleFilled;
+    fn solve_report(&self) -> SolveReport {
+        let mut report = SolveReport::default();
+        for (index, layer) in self.iter_layers() {
+            if self.iter_connected_layers(index).unwrap().count() != layer.connections.len() {
+                report.open_ends.push(index);
+            }
+            match layer.fill.iter().take(2).count() {
+                0 => report.unfilled.push(index),
+                1 => {}
+                _ => report.double_filled.push(index),
+            }
         }
 
-        fn has_duplicate_fill(slf: &Grid<Cell>) -> bool {
-            let mut source_counts = HashMap::new();
-            for cell in slf.iter() {
-                if let Some(source) = cell.1.source() {
-                    match source_counts.entry(source) {
-                        std::collections::hash_map::Entry::Occupied(mut entry) => {
-                            *entry.get_mut() += 1
-                        }
-                        std::collections::hash_map::Entry::Vacant(entry) => {
-                            entry.insert(1);
-                        }
-                    }
-                }
-            }
-            for (color, count) in source_counts.into_iter().filter(|(_, count)| *count > 1) {
-                let any_source = slf
-                    .iter()
-                    .find(|(_, cell)| cell.source() == Some(color))
-                    .unwrap();
-                let all_connected = slf
-                    .all_connected(GameGridIndex {
-                        grid_index: any_source.0,
-                        layer_index: 0,
-                    })
-                    .unwrap();
-                // Assume that double_filled is false
-                let source_count = all_connected
+        let regions = self.regions();
+        for (i, region) in regions.iter().enumerate() {
+            for color in region.fill.iter() {
+                let split = regions
                     .iter()
-                    .filter_map(|index| slf.get(index.grid_index).and_then(|c| c.source()))
-                    .count();
-                if source_count != count {
-                    return true;
+                    .enumerate()
+                    .any(|(j, other)| j != i && other.fill.contains(color));
+                if split {
+                    report.split_regions.push(SplitRegion {
+                        color,
+                        layers: region.layers.clone(),
+                    });
                 }
             }
-            false
         }
-        if has_duplicate_fill(self) {
-            return GridSolveState::DuplicateColorSection;
-        }
-
-        GridSolveState::Solved
+        report
     }
 
     fn get_layer(&self, index: GameGridIndex) -> Option<&CellLayer> {
@@ -177,31 +303,75 @@ impl GameGrid for Grid<Cell> {
     }
 
     fn fill(&mut self) {
-        self.iter_mut().for_each(|c| c.1.clear_fill());
-        let mut to_explore_queue: VecDeque<_> = self
-            .iter()
-            .filter_map(|(grid_index, cell)| {
-                cell.source().map(|source| {
-                    (
-                        GameGridIndex {
+        let connectivity = Connectivity::new(self);
+        self.fill_from(&connectivity);
+    }
+
+    fn fill_from(&mut self, connectivity: &Connectivity) {
+        for (grid_index, cell) in self.iter_mut() {
+            for (layer_index, layer) in cell.layers_mut().iter_mut().enumerate() {
+                layer.fill = connectivity.fill(GameGridIndex {
+                    grid_index,
+                    layer_index,
+                });
+            }
+        }
+    }
+
+    fn fill_after_swap(&mut self, a: GridIndex, b: GridIndex) {
+        // A region can only have changed if it holds a swapped tile, or held one and was
+        // cut off from it, so it has a layer on a swapped tile or one leading into it.
+        let mut seeds = Vec::with_capacity(12);
+        for index in [a, b] {
+            let Some(cell) = self.get(index) else {
+                continue;
+            };
+            seeds.extend(
+                (0..cell.iter_layers().count()).map(|layer_index| GameGridIndex {
+                    grid_index: index,
+                    layer_index,
+                }),
+            );
+            for direction in Direction::ALL {
+                if let Some((grid_index, neighbor)) = self.get_neighbor(index, direction) {
+                    if let Some((layer_index, _)) =
+                        neighbor.get_layer_for_direction(direction.inverse())
+                    {
+                        seeds.push(GameGridIndex {
                             grid_index,
-                            layer_index: 0,
-                        },
-                        ColorSet::singleton(source),
-                    )
-                })
-            })
-            .collect();
-        while let Some((to_explore, to_fill)) = to_explore_queue.pop_front() {
-            let cell = self.get_layer_mut(to_explore).unwrap();
-            let union = cell.fill.union(to_fill);
-            if union != cell.fill {
-                cell.fill = union;
-                for (neighbor, layer) in self.iter_connected_layers(to_explore).unwrap() {
-                    if union != layer.fill {
-                        to_explore_queue.push_back((neighbor, union))
+                            layer_index,
+                        });
+                    }
+                }
+            }
+        }
+
+        let slots = LayerSlots::new(self.size());
+        let mut visited = vec![false; slots.count()];
+        let mut region = Vec::new();
+        for seed in seeds {
+            if std::mem::replace(&mut visited[slots.of(seed)], true) {
+                continue;
+            }
+            region.clear();
+            region.push(seed);
+            let mut fill = ColorSet::empty();
+            let mut next = 0;
+            while let Some(&exploring) = region.get(next) {
+                next += 1;
+                if exploring.layer_index == 0 {
+                    if let Some(source) = self.get(exploring.grid_index).and_then(Cell::source) {
+                        fill.insert(source);
                     }
                 }
+                for (neighbor, _) in self.iter_connected_layers(exploring).unwrap() {
+                    if !std::mem::replace(&mut visited[slots.of(neighbor)], true) {
+                        region.push(neighbor);
+                    }
+                }
+            }
+            for &index in region.iter() {
+                self.get_layer_mut(index).unwrap().fill = fill;
             }
         }
     }
@@ -249,7 +419,7 @@ impl GameGrid for Grid<Cell> {
         to_explore.push(index);
         while let Some(exploring) = to_explore.pop() {
             explored.insert(exploring);
-            for layer in self.iter_connected_layers(index).unwrap() {
+            for layer in self.iter_connected_layers(exploring).unwrap() {
                 if !explored.contains(&layer.0) {
                     to_explore.push(layer.0)
                 }
@@ -257,4 +427,329 @@ impl GameGrid for Grid<Cell> {
         }
         Some(explored)
     }
+
+    fn regions(&self) -> Vec<Region> {
+        let mut explored = HashSet::new();
+        let mut regions = Vec::new();
+        for (start, _) in self.iter_layers() {
+            if !explored.insert(start) {
+                continue;
+            }
+            let mut layers = Vec::new();
+            let mut to_explore = vec![start];
+            while let Some(exploring) = to_explore.pop() {
+                layers.push(exploring);
+                for (neighbor, _) in self.iter_connected_layers(exploring).unwrap() {
+                    if explored.insert(neighbor) {
+                        to_explore.push(neighbor);
+                    }
+                }
+            }
+            layers.sort_by_key(|index| (index.grid_index.y, index.grid_index.x, index.layer_index));
+
+            let mut region = Region {
+                sources: Vec::new(),
+                fill: ColorSet::empty(),
+                open_ends: Vec::new(),
+                layers,
+            };
+            for &index in region.layers.iter() {
+                let cell = self.get(index.grid_index).unwrap();
+                if let Some(source) = cell.source().filter(|_| index.layer_index == 0) {
+                    region.sources.push((index.grid_index, source));
+                    region.fill.insert(source);
+                }
+                let layer = cell.get_layer(index.layer_index).unwrap();
+                for direction in layer.connections.iter_set() {
+                    let met = self.get_neighbor(index.grid_index, direction).is_some_and(
+                        |(_, neighbor)| {
+                            neighbor
+                                .get_layer_for_direction(direction.inverse())
+                                .is_some()
+                        },
+                    );
+                    if !met {
+                        region.open_ends.push((index, direction));
+                    }
+                }
+            }
+            regions.push(region);
+        }
+        regions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{debug_puzzle, Puzzle};
+
+    fn grid_for(puzzle: Puzzle) -> Grid<Cell> {
+        Grid::from_puzzle_grid(puzzle.start().clone())
+    }
+
+    fn layer(x: usize, y: usize, layer_index: usize) -> GameGridIndex {
+        GameGridIndex {
+            grid_index: GridIndex::new(x, y),
+            layer_index,
+        }
+    }
+
+    fn region_of(regions: &[Region], index: GameGridIndex) -> &Region {
+        regions
+            .iter()
+            .find(|region| region.layers.contains(&index))
+            .expect("every layer is in a region")
+    }
+
+    #[test]
+    fn regions_cover_every_layer_once() {
+        for puzzle in [debug_puzzle::test_puzzle(), debug_puzzle::debug_puzzle()] {
+            let grid = grid_for(puzzle);
+            let regions = grid.regions();
+            let mut covered: Vec<_> = regions
+                .iter()
+                .flat_map(|region| region.layers.iter().copied())
+                .collect();
+            let mut layers: Vec<_> = grid.iter_layers().map(|(index, _)| index).collect();
+            covered.sort_by_key(|index| (index.grid_index, index.layer_index));
+            layers.sort_by_key(|index| (index.grid_index, index.layer_index));
+            assert_eq!(covered, layers);
+        }
+    }
+
+    #[test]
+    fn test_puzzle_regions() {
+        let regions = grid_for(debug_puzzle::test_puzzle()).regions();
+        assert_eq!(regions.len(), 3);
+
+        assert_eq!(regions[0].layers, [layer(0, 0, 0)]);
+        assert_eq!(regions[0].open_ends, [(layer(0, 0, 0), Direction::N)]);
+
+        assert_eq!(regions[1].layers, [layer(0, 1, 0), layer(0, 2, 0)]);
+        assert!(regions[1].sources.is_empty());
+        assert!(regions[1].fill.is_empty());
+        assert_eq!(
+            regions[1].open_ends,
+            [
+                (layer(0, 1, 0), Direction::N),
+                (layer(0, 2, 0), Direction::S)
+            ]
+        );
+
+        assert_eq!(regions[2].sources, [(GridIndex::new(0, 3), Color::Purple)]);
+        assert_eq!(regions[2].fill, ColorSet::singleton(Color::Purple));
+    }
+
+    #[test]
+    fn debug_puzzle_regions() {
+        let grid = grid_for(debug_puzzle::debug_puzzle());
+        let regions = grid.regions();
+
+        // A path through several tiles.
+        let path = region_of(&regions, layer(2, 0, 0));
+        assert_eq!(
+            path.layers,
+            [
+                layer(2, 0, 0),
+                layer(3, 0, 0),
+                layer(4, 0, 0),
+                layer(5, 0, 0)
+            ]
+        );
+        assert_eq!(path.open_ends.len(), 5);
+
+        // Two sources of different colors joined together.
+        let joined = region_of(&regions, layer(3, 2, 0));
+        assert_eq!(
+            joined.sources,
+            [
+                (GridIndex::new(3, 2), Color::Yellow),
+                (GridIndex::new(4, 4), Color::Orange)
+            ]
+        );
+        assert!(joined.fill.contains(Color::Yellow) && joined.fill.contains(Color::Orange));
+
+        // The layers of an intersection can be in different regions.
+        let first = region_of(&regions, layer(7, 0, 0));
+        let second = region_of(&regions, layer(7, 0, 1));
+        assert_ne!(first, second);
+        assert_eq!(first.layers.len(), 9);
+        assert_eq!(second.layers, [layer(7, 0, 1), layer(7, 1, 0)]);
+
+        // A tile with no connections is a region of its own with no open ends.
+        let lone = region_of(&regions, layer(0, 0, 0));
+        assert_eq!(lone.layers, [layer(0, 0, 0)]);
+        assert!(lone.open_ends.is_empty());
+    }
+
+    #[test]
+    fn all_connected_matches_regions() {
+        let grid = grid_for(debug_puzzle::debug_puzzle());
+        let regions = grid.regions();
+        for (index, _) in grid.iter_layers() {
+            let connected = grid.all_connected(index).unwrap();
+            let region = region_of(&regions, index);
+            assert_eq!(connected.len(), region.layers.len(), "{index:?}");
+            assert!(region.layers.iter().all(|layer| connected.contains(layer)));
+        }
+    }
+
+    #[test]
+    fn split_sources_are_a_duplicate_color_section() {
+        let puzzle: Puzzle = "swaps: 1\nhint: 0 0\ngrid:\nE@p W E@p W\n".parse().unwrap();
+        let grid = grid_for(puzzle);
+        assert_eq!(grid.regions().len(), 2);
+        assert_eq!(grid.is_solved(), GridSolveState::DuplicateColorSection);
+        let report = grid.solve_report();
+        assert_eq!(report.state(), GridSolveState::DuplicateColorSection);
+        assert_eq!(report.split_regions.len(), 2);
+    }
+
+    fn fills(grid: &Grid<Cell>) -> Vec<(GameGridIndex, ColorSet)> {
+        grid.iter_layers()
+            .map(|(index, layer)| (index, layer.fill))
+            .collect()
+    }
+
+    fn assert_matches_full_fill(grid: &Grid<Cell>) {
+        let mut full = grid.clone();
+        full.fill();
+        assert_eq!(fills(grid), fills(&full));
+    }
+
+    #[test]
+    fn fill_after_swap_matches_full_fill() {
+        for puzzle in [debug_puzzle::test_puzzle(), debug_puzzle::debug_puzzle()] {
+            let grid = grid_for(puzzle);
+            let cells: Vec<_> = grid.iter().map(|(index, _)| index).collect();
+            for (i, &a) in cells.iter().enumerate() {
+                for &b in cells.iter().skip(i + 1) {
+                    let mut swapped = grid.clone();
+                    let Some(record) = swapped.swap_with_rotation(a, b) else {
+                        continue;
+                    };
+                    swapped.fill_after_swap(a, b);
+                    assert_matches_full_fill(&swapped);
+
+                    swapped.undo_swap(record);
+                    swapped.fill_after_swap(a, b);
+                    assert_eq!(fills(&swapped), fills(&grid));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fill_after_many_swaps_matches_full_fill() {
+        // A long chain of swaps splits and joins regions many times over.
+        let mut grid = grid_for(debug_puzzle::debug_puzzle());
+        let cells: Vec<_> = grid.iter().map(|(index, _)| index).collect();
+        let mut made = 0;
+        for step in 0..2000 {
+            let a = cells[(step * 7) % cells.len()];
+            let b = cells[(step * step + 5) % cells.len()];
+            if grid.swap_with_rotation(a, b).is_some() {
+                grid.fill_after_swap(a, b);
+                assert_matches_full_fill(&grid);
+                made += 1;
+            }
+        }
+        assert!(made > 100, "only {made} swaps were made");
+    }
+
+    #[test]
+    fn connectivity_matches_regions() {
+        let mut grid = grid_for(debug_puzzle::debug_puzzle());
+        let cells: Vec<_> = grid.iter().map(|(index, _)| index).collect();
+        for step in 0..500 {
+            let a = cells[(step * 7) % cells.len()];
+            let b = cells[(step * step + 5) % cells.len()];
+            if grid.swap_with_rotation(a, b).is_none() {
+                continue;
+            }
+            grid.fill();
+
+            let connectivity = Connectivity::new(&grid);
+            let regions = grid.regions();
+            for region in regions.iter() {
+                for &index in region.layers.iter() {
+                    assert_eq!(connectivity.fill(index), region.fill);
+                    assert!(connectivity.same_region(index, region.layers[0]));
+                }
+            }
+            let open_ends: usize = regions.iter().map(|region| region.open_ends.len()).sum();
+            assert_eq!(connectivity.open_ends(), open_ends);
+            assert_eq!(grid.is_solved(), grid.solve_report().state());
+            assert_eq!(grid.is_solved(), is_solved_by_regions(&grid));
+        }
+    }
+
+    fn report_for(grid: &str) -> SolveReport {
+        let puzzle: Puzzle = format!("swaps: 1\nhint: 0 0\ngrid:\n{grid}")
+            .parse()
+            .unwrap();
+        grid_for(puzzle).solve_report()
+    }
+
+    #[test]
+    fn solved_grids_report_nothing() {
+        assert_eq!(report_for("E@p W\n"), SolveReport::default());
+        assert_eq!(
+            report_for(".   S@p           .\nE@b +E0N1W0S1 W\n.   N             .\n"),
+            SolveReport::default()
+        );
+    }
+
+    #[test]
+    fn open_ends_and_unfilled_layers_are_listed() {
+        assert_eq!(
+            report_for("E@p W E\n"),
+            SolveReport {
+                open_ends: vec![layer(2, 0, 0)],
+                unfilled: vec![layer(2, 0, 0)],
+                ..Default::default()
+            }
+        );
+        // The crossing's second layer loses its end; the first stays joined up.
+        assert_eq!(
+            report_for(".   S@p           .\nE@b +E0N1W0S1 W\n.   -             .\n"),
+            SolveReport {
+                open_ends: vec![layer(1, 1, 1)],
+                unfilled: vec![layer(1, 2, 0)],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn double_filled_layers_are_listed() {
+        assert_eq!(
+            report_for("E@p EW W@b E@g W\n"),
+            SolveReport {
+                double_filled: vec![layer(0, 0, 0), layer(1, 0, 0), layer(2, 0, 0)],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn split_regions_are_listed() {
+        assert_eq!(
+            report_for("E@p W E@p W E@b W\n"),
+            SolveReport {
+                split_regions: vec![
+                    SplitRegion {
+                        color: Color::Purple,
+                        layers: vec![layer(0, 0, 0), layer(1, 0, 0)],
+                    },
+                    SplitRegion {
+                        color: Color::Purple,
+                        layers: vec![layer(2, 0, 0), layer(3, 0, 0)],
+                    },
+                ],
+                ..Default::default()
+            }
+        );
+    }
 }
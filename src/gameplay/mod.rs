@@ -1,17 +1,35 @@
 mod cell;
 mod color;
 mod color_set;
+mod connectivity;
 pub mod debug_puzzle;
+mod difficulty;
 mod fcolor;
 mod game_grid;
+mod move_notation;
+mod move_tree;
 mod playing_puzzle;
 mod puzzle;
+mod puzzle_code;
+mod puzzle_text;
+mod puzzle_validation;
+mod replay;
 mod swap_record;
 pub use cell::{Cell, CellLayer};
 pub use color::Color;
 pub use color_set::ColorSet;
+pub use connectivity::Connectivity;
+pub use difficulty::Difficulty;
 pub use fcolor::FColor;
-pub use game_grid::{GameGrid, GridSolveState};
+pub use game_grid::{
+    is_solved_by_regions, GameGrid, GameGridIndex, GridSolveState, Region, SolveReport, SplitRegion,
+};
+pub use move_notation::{parse_square, square_name, SwapNotationError};
+pub use move_tree::{MoveNode, MoveTree};
 pub use playing_puzzle::{PlayingPuzzle, PuzzleSolveState};
-pub use puzzle::{fallback_puzzle, LayerConnection, Puzzle, PuzzleCell};
+pub use puzzle::{fallback_puzzle, LayerConnection, Puzzle, PuzzleCell, MAX_PUZZLE_SIDE};
+pub use puzzle_code::PuzzleCodeError;
+pub use puzzle_text::PuzzleTextError;
+pub use puzzle_validation::PuzzleError;
+pub use replay::{Replay, ReplayAction, ReplayError, ReplayEvent, ReplayPlayer};
 pub use swap_record::SwapRecord;
@@ -0,0 +1,228 @@
+//! Move notation for swaps.
+//!
+//! A square is named by its column letter and row number, e.g. `B2` for x = 1, y = 1.
+//! Columns go `A`..`Z`, then `AA`, `AB` and so on; row 1 is the top row.
+//!
+//! A swap is the two squares joined by `-`, e.g. `B2-C3`. A marker after a square says how
+//! the tile that started there turns as it moves: `>` clockwise, `<` counter-clockwise and
+//! `~` half a turn. `B2>-C3` moves the tile on B2 to C3, turning it clockwise.
+
+use std::{fmt, str::FromStr};
+
+use crate::grids::{GridIndex, Rotation};
+
+use super::SwapRecord;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwapNotationError {
+    MissingSeparator,
+    InvalidSquare(String),
+    SameSquare,
+}
+
+impl fmt::Display for SwapNotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwapNotationError::MissingSeparator => {
+                f.write_str("a move is two squares joined by `-`")
+            }
+            SwapNotationError::InvalidSquare(square) => write!(f, "invalid square `{square}`"),
+            SwapNotationError::SameSquare => f.write_str("a move swaps two different squares"),
+        }
+    }
+}
+
+impl std::error::Error for SwapNotationError {}
+
+const fn rotation_marker(rotation: Rotation) -> Option<char> {
+    match rotation {
+        Rotation::None => None,
+        Rotation::CounterClockwise => Some('<'),
+        Rotation::Half => Some('~'),
+        Rotation::Clockwise => Some('>'),
+    }
+}
+
+fn rotation_for_marker(c: char) -> Option<Rotation> {
+    [
+        Rotation::CounterClockwise,
+        Rotation::Half,
+        Rotation::Clockwise,
+    ]
+    .into_iter()
+    .find(|rotation| rotation_marker(*rotation) == Some(c))
+}
+
+/// The name of the square at `index`, e.g. `B2`.
+pub fn square_name(index: GridIndex) -> String {
+    let mut letters = Vec::new();
+    let mut column = index.x + 1;
+    while column > 0 {
+        column -= 1;
+        letters.push((b'A' + (column % 26) as u8) as char);
+        column /= 26;
+    }
+    let column: String = letters.into_iter().rev().collect();
+    format!("{column}{}", index.y + 1)
+}
+
+/// Parses a square name made by [`square_name`]. Column letters may be lowercase.
+pub fn parse_square(text: &str) -> Option<GridIndex> {
+    let digits = text.find(|c: char| c.is_ascii_digit())?;
+    let (letters, row) = text.split_at(digits);
+    if letters.is_empty() || !row.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let mut column = 0usize;
+    for c in letters.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        let value = (c.to_ascii_uppercase() as u8 - b'A') as usize + 1;
+        column = column.checked_mul(26)?.checked_add(value)?;
+    }
+    let row: usize = row.parse().ok()?;
+    Some(GridIndex::new(column - 1, row.checked_sub(1)?))
+}
+
+fn parse_side(text: &str) -> Result<(GridIndex, Rotation), SwapNotationError> {
+    let text = text.trim();
+    let invalid = || SwapNotationError::InvalidSquare(text.to_owned());
+    let (square, rotation) = match text.chars().last().and_then(rotation_for_marker) {
+        Some(rotation) => (&text[..text.len() - 1], rotation),
+        None => (text, Rotation::None),
+    };
+    Ok((parse_square(square).ok_or_else(invalid)?, rotation))
+}
+
+impl fmt::Display for SwapRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&square_name(self.a))?;
+        if let Some(marker) = rotation_marker(self.a_rotation) {
+            write!(f, "{marker}")?;
+        }
+        write!(f, "-{}", square_name(self.b))?;
+        if let Some(marker) = rotation_marker(self.b_rotation) {
+            write!(f, "{marker}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SwapRecord {
+    type Err = SwapNotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (a, b) = s
+            .trim()
+            .split_once('-')
+            .ok_or(SwapNotationError::MissingSeparator)?;
+        let (a, a_rotation) = parse_side(a)?;
+        let (b, b_rotation) = parse_side(b)?;
+        if a == b {
+            return Err(SwapNotationError::SameSquare);
+        }
+        Ok(SwapRecord::new(a, b, a_rotation, b_rotation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROTATIONS: [Rotation; 4] = [
+        Rotation::None,
+        Rotation::CounterClockwise,
+        Rotation::Half,
+        Rotation::Clockwise,
+    ];
+
+    #[test]
+    fn squares_are_named_by_column_and_row() {
+        for (x, y, name) in [
+            (0, 0, "A1"),
+            (1, 1, "B2"),
+            (25, 0, "Z1"),
+            (26, 0, "AA1"),
+            (27, 4, "AB5"),
+            (701, 9, "ZZ10"),
+            (702, 0, "AAA1"),
+        ] {
+            assert_eq!(square_name(GridIndex::new(x, y)), name);
+            assert_eq!(parse_square(name), Some(GridIndex::new(x, y)));
+        }
+        assert_eq!(parse_square("ab5"), Some(GridIndex::new(27, 4)));
+    }
+
+    #[test]
+    fn square_names_round_trip() {
+        for x in 0..2000 {
+            for y in [0, 1, 99] {
+                let index = GridIndex::new(x, y);
+                assert_eq!(parse_square(&square_name(index)), Some(index));
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_squares_are_rejected() {
+        for text in [
+            "",
+            "A",
+            "1",
+            "A0",
+            "2A",
+            "A1B",
+            "A-1",
+            "A 1",
+            "É1",
+            "ZZZZZZZZZZZZZZZZ1",
+            "A99999999999999999999999",
+        ] {
+            assert_eq!(parse_square(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn swaps_round_trip() {
+        let a = GridIndex::new(1, 1);
+        let b = GridIndex::new(2, 2);
+        for a_rotation in ROTATIONS {
+            for b_rotation in ROTATIONS {
+                let record = SwapRecord::new(a, b, a_rotation, b_rotation);
+                assert_eq!(record.to_string().parse(), Ok(record));
+            }
+        }
+    }
+
+    #[test]
+    fn example_swap_parses() {
+        let record = SwapRecord::new(
+            GridIndex::new(1, 1),
+            GridIndex::new(2, 2),
+            Rotation::Clockwise,
+            Rotation::None,
+        );
+        assert_eq!(record.to_string(), "B2>-C3");
+        assert_eq!(" b2> - c3 ".parse(), Ok(record));
+    }
+
+    #[test]
+    fn invalid_swaps_are_rejected() {
+        let parse = |text: &str| text.parse::<SwapRecord>();
+        assert_eq!(parse("B2 C3"), Err(SwapNotationError::MissingSeparator));
+        assert_eq!(parse("B2-B2>"), Err(SwapNotationError::SameSquare));
+        assert_eq!(
+            parse("B2-C"),
+            Err(SwapNotationError::InvalidSquare("C".to_owned()))
+        );
+        assert_eq!(
+            parse("B2>>-C3"),
+            Err(SwapNotationError::InvalidSquare("B2>>".to_owned()))
+        );
+        assert_eq!(
+            parse("-C3"),
+            Err(SwapNotationError::InvalidSquare(String::new()))
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use super::SwapRecord;
+
+/// A swap and the move it was made after, `None` for the start of the puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MoveNode {
+    pub parent: Option<usize>,
+    pub record: SwapRecord,
+}
+
+/// Every line of swaps tried on a puzzle. Undoing keeps the undone moves, and a different
+/// swap from an earlier position starts a new branch instead of replacing them.
+///
+/// Moves are identified by their index; `None` is the start of the puzzle.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MoveTree {
+    nodes: Vec<MoveNode>,
+    current: Option<usize>,
+    // The end of the line that redo follows. `current` is always on the way to it.
+    tip: Option<usize>,
+}
+
+impl MoveTree {
+    /// Whether every index is in range and every parent comes before its children, which a
+    /// deserialized tree does not guarantee.
+    pub fn is_consistent(&self) -> bool {
+        let in_range = |id: Option<usize>| id.map_or(true, |id| id < self.nodes.len());
+        self.nodes
+            .iter()
+            .enumerate()
+            .all(|(id, node)| node.parent.map_or(true, |parent| parent < id))
+            && in_range(self.current)
+            && in_range(self.tip)
+            && (self.current.is_none()
+                || self.ancestors(self.tip).any(|id| Some(id) == self.current))
+    }
+
+    pub const fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn get(&self, id: usize) -> Option<&MoveNode> {
+        self.nodes.get(id)
+    }
+
+    /// The number of swaps from the start to `id`.
+    pub fn depth(&self, id: Option<usize>) -> usize {
+        self.ancestors(id).count()
+    }
+
+    /// `id` and the moves before it, back to the start.
+    pub fn ancestors(&self, id: Option<usize>) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(id, |id| self.nodes[*id].parent)
+    }
+
+    pub fn children(&self, id: Option<usize>) -> impl Iterator<Item = usize> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.parent == id)
+            .map(|(child, _)| child)
+    }
+
+    /// Whether `id` has been made on the way to the current position.
+    pub fn is_applied(&self, id: usize) -> bool {
+        self.ancestors(self.current).any(|ancestor| ancestor == id)
+    }
+
+    /// The child of `id` that the redo line continues through: towards the tip if `id`
+    /// is on the way to it, otherwise the newest.
+    pub fn line_child(&self, id: Option<usize>) -> Option<usize> {
+        self.ancestors(self.tip)
+            .find(|ancestor| self.nodes[*ancestor].parent == id)
+            .or_else(|| self.children(id).last())
+    }
+
+    /// The swaps on the line through the current position, from the start to the tip.
+    pub fn line(&self) -> Vec<SwapRecord> {
+        let mut line: Vec<SwapRecord> = self
+            .ancestors(self.tip)
+            .map(|id| self.nodes[id].record)
+            .collect();
+        line.reverse();
+        line
+    }
+
+    /// Records a swap made from the current position. Repeating a swap that was already
+    /// tried from here returns to that branch.
+    pub fn push(&mut self, record: SwapRecord) -> usize {
+        let existing = self
+            .children(self.current)
+            .find(|child| self.nodes[*child].record == record);
+        let id = existing.unwrap_or_else(|| {
+            self.nodes.push(MoveNode {
+                parent: self.current,
+                record,
+            });
+            self.nodes.len() - 1
+        });
+        self.move_to(Some(id));
+        id
+    }
+
+    /// Moves back to the parent of the current position, returning the undone swap.
+    pub fn undo(&mut self) -> Option<SwapRecord> {
+        let current = self.current?;
+        self.current = self.nodes[current].parent;
+        Some(self.nodes[current].record)
+    }
+
+    /// Moves forward along the line, returning the redone swap.
+    pub fn redo(&mut self) -> Option<SwapRecord> {
+        let next = self.line_child(self.current)?;
+        self.current = Some(next);
+        Some(self.nodes[next].record)
+    }
+
+    /// Sets the current position, e.g. after the grid has been moved there. The tip is kept
+    /// if the position is on its line; otherwise the line follows the newest branches.
+    pub(super) fn move_to(&mut self, id: Option<usize>) {
+        if let Some(id) = id {
+            if !self.ancestors(self.tip).any(|ancestor| ancestor == id) {
+                let mut tip = id;
+                while let Some(child) = self.children(Some(tip)).last() {
+                    tip = child;
+                }
+                self.tip = Some(tip);
+            }
+        }
+        self.current = id;
+    }
+
+    /// The path from the current position to `target`: the moves to undo, nearest first,
+    /// then the moves to redo, in order.
+    pub fn path_to(&self, target: Option<usize>) -> (Vec<usize>, Vec<usize>) {
+        let target_ancestors: Vec<usize> = self.ancestors(target).collect();
+        let undo: Vec<usize> = self
+            .ancestors(self.current)
+            .take_while(|id| !target_ancestors.contains(id))
+            .collect();
+        let common = self
+            .ancestors(self.current)
+            .find(|id| target_ancestors.contains(id));
+        let mut redo: Vec<usize> = target_ancestors
+            .into_iter()
+            .take_while(|id| Some(*id) != common)
+            .collect();
+        redo.reverse();
+        (undo, redo)
+    }
+}
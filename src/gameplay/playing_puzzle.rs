@@ -1,6 +1,6 @@
 use crate::grids::{Grid, GridIndex, GridSize};
 
-use super::{game_grid::GridSolveState, Cell, GameGrid, Puzzle, SwapRecord};
+use super::{game_grid::GridSolveState, Cell, GameGrid, MoveTree, Puzzle, SolveReport, SwapRecord};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde:: Deserialize)]
 pub enum PuzzleSolveState {
@@ -16,7 +16,7 @@ pub enum PuzzleSolveState {
 pub struct PlayingPuzzle {
     puzzle: Puzzle,
     grid: Grid<Cell>,
-    history: Vec<SwapRecord>,
+    moves: MoveTree,
 }
 
 impl PlayingPuzzle {
@@ -25,10 +25,42 @@ impl PlayingPuzzle {
         Self {
             puzzle,
             grid,
-            history: Vec::new(),
+            moves: MoveTree::default(),
         }
     }
 
+    /// Continues a puzzle from a single line of swaps, of which the first `applied` are made.
+    /// The line is cut at the first swap that no longer plays out the same way.
+    pub fn resume(puzzle: Puzzle, line: Vec<SwapRecord>, applied: usize) -> Self {
+        let mut playing = Self::play(puzzle);
+        for record in line {
+            match playing.grid.swap_with_rotation(record.a, record.b) {
+                Some(made) if made == record => {
+                    playing.grid.fill_after_swap(record.a, record.b);
+                    playing.moves.push(record);
+                }
+                Some(made) => {
+                    playing.grid.undo_swap(made);
+                    playing.grid.fill_after_swap(made.a, made.b);
+                    break;
+                }
+                None => break,
+            }
+        }
+        while playing.swaps_made() > applied {
+            playing.try_undo();
+        }
+        playing
+    }
+
+    /// Whether the grid and move history agree with their own bookkeeping, which a
+    /// deserialized save does not guarantee.
+    pub fn is_consistent(&self) -> bool {
+        self.grid.is_consistent()
+            && self.grid.size() == self.puzzle.start().size()
+            && self.moves.is_consistent()
+    }
+
     pub const fn puzzle(&self) -> &Puzzle {
         &self.puzzle
     }
@@ -38,7 +70,7 @@ impl PlayingPuzzle {
     }
 
     pub fn swaps_made(&self) -> usize {
-        self.history.len()
+        self.moves.depth(self.moves.current())
     }
 
     pub fn swap_limit(&self) -> usize {
@@ -57,10 +89,15 @@ impl PlayingPuzzle {
         self.grid.iter()
     }
 
+    /// Every swap tried on this puzzle, including undone moves and other branches.
+    pub const fn moves(&self) -> &MoveTree {
+        &self.moves
+    }
+
     pub fn try_swap(&mut self, a: GridIndex, b: GridIndex) -> Option<SwapRecord> {
         if let Some(record) = self.grid.swap_with_rotation(a, b) {
-            self.history.push(record);
-            self.grid.fill();
+            self.moves.push(record);
+            self.grid.fill_after_swap(a, b);
             Some(record)
         } else {
             None
@@ -68,13 +105,45 @@ impl PlayingPuzzle {
     }
 
     pub fn try_undo(&mut self) -> Option<SwapRecord> {
-        if let Some(record) = self.history.pop() {
+        let record = self.moves.undo()?;
+        self.grid.undo_swap(record);
+        self.grid.fill_after_swap(record.a, record.b);
+        Some(record)
+    }
+
+    pub fn try_redo(&mut self) -> Option<SwapRecord> {
+        let record = self.moves.redo()?;
+        self.redo_swap(record);
+        Some(record)
+    }
+
+    fn redo_swap(&mut self, record: SwapRecord) {
+        // The grid is back in the state the swap was first made from, so the cells rotate
+        // the same way again.
+        let redone = self.grid.swap_with_rotation(record.a, record.b);
+        debug_assert_eq!(redone, Some(record));
+        self.grid.fill_after_swap(record.a, record.b);
+    }
+
+    /// Undoes and redoes swaps until the grid is at `target`, a move in [`Self::moves`] or
+    /// `None` for the start. Returns the swaps as applied to the grid, with undone swaps
+    /// inverted.
+    pub fn jump_to(&mut self, target: Option<usize>) -> Vec<SwapRecord> {
+        let (undo, redo) = self.moves.path_to(target);
+        let mut applied = Vec::new();
+        for id in undo {
+            let record = self.moves.get(id).expect("path is within the tree").record;
             self.grid.undo_swap(record);
-            self.grid.fill();
-            Some(record)
-        } else {
-            None
+            self.grid.fill_after_swap(record.a, record.b);
+            applied.push(record.inverse());
+        }
+        for id in redo {
+            let record = self.moves.get(id).expect("path is within the tree").record;
+            self.redo_swap(record);
+            applied.push(record);
         }
+        self.moves.move_to(target);
+        applied
     }
 
     pub fn is_solved(&self) -> PuzzleSolveState {
@@ -90,4 +159,9 @@ impl PlayingPuzzle {
             GridSolveState::DuplicateColorSection => PuzzleSolveState::DuplicateColorSection,
         }
     }
+
+    /// Every problem with the grid as it stands. Running out of swaps is not included.
+    pub fn solve_report(&self) -> SolveReport {
+        self.grid.solve_report()
+    }
 }
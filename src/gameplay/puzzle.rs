@@ -1,6 +1,10 @@
 use crate::grids::{Direction, DirectionMap, DirectionSet, Grid, GridIndex, GridSize};
 
-use super::Color;
+use super::{Color, Difficulty};
+
+/// The largest width or height of a puzzle. Larger grids are not generated, and codes for
+/// them are rejected, so a short code cannot ask for a huge allocation.
+pub const MAX_PUZZLE_SIDE: usize = 64;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde:: Deserialize)]
 pub enum LayerConnection {
@@ -67,16 +71,40 @@ impl PuzzleCell {
     }
 }
 
-#[derive(serde::Serialize, serde:: Deserialize, Debug, Clone)]
+#[derive(serde::Serialize, serde:: Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Puzzle {
     grid: Grid<PuzzleCell>,
     swaps: u8,
     hint: GridIndex,
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    difficulty: Option<Difficulty>,
 }
 
 impl Puzzle {
     pub fn new(grid: Grid<PuzzleCell>, swaps: u8, hint: GridIndex) -> Self {
-        Self { grid, swaps, hint }
+        Self {
+            grid,
+            swaps,
+            hint,
+            seed: None,
+            difficulty: None,
+        }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    pub fn with_difficulty(self, difficulty: Difficulty) -> Self {
+        Self {
+            difficulty: Some(difficulty),
+            ..self
+        }
     }
 
     pub fn swap_limit(&self) -> u8 {
@@ -90,6 +118,16 @@ impl Puzzle {
     pub const fn hint(&self) -> GridIndex {
         self.hint
     }
+
+    /// The seed this puzzle was generated from, if it was generated.
+    pub const fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The estimated difficulty, if it was measured when the puzzle was generated.
+    pub const fn difficulty(&self) -> Option<Difficulty> {
+        self.difficulty
+    }
 }
 
 pub fn fallback_puzzle() -> Puzzle {
@@ -100,7 +138,7 @@ pub fn fallback_puzzle() -> Puzzle {
     grid.insert(
         GridIndex { x: 0, y: 0 },
         PuzzleCell::Source {
-            connections: DirectionSet::from_iter([Direction::W].into_iter()),
+            connections: DirectionSet::from_iter([Direction::W]),
             source: Color::SWAP,
         },
     )
@@ -108,7 +146,7 @@ pub fn fallback_puzzle() -> Puzzle {
     grid.insert(
         GridIndex { x: 1, y: 0 },
         PuzzleCell::Normal {
-            connections: DirectionSet::from_iter([Direction::E].into_iter()),
+            connections: DirectionSet::from_iter([Direction::E]),
         },
     )
     .unwrap();
@@ -116,5 +154,7 @@ pub fn fallback_puzzle() -> Puzzle {
         grid,
         swaps: 1,
         hint: GridIndex { x: 1, y: 0 },
+        seed: None,
+        difficulty: None,
     }
 }
@@ -0,0 +1,374 @@
+//! Compact, URL-safe codes for sharing puzzles.
+//!
+//! A code is URL-safe base64 (without padding) of:
+//!
+//! - a version byte,
+//! - width, height, hint x and hint y as LEB128 integers,
+//! - the swap limit,
+//! - a flags byte, followed by the seed (8 bytes, little endian) and difficulty if flagged,
+//! - one or two bytes per cell in row order. The top two bits of the first byte are the
+//!   cell kind; normal and source cells keep their connection bits in the low nibble,
+//!   sources add a color byte, and intersections add a byte with two bits per direction.
+
+use std::fmt;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::grids::{Direction, DirectionMap, DirectionSet, Grid, GridIndex, GridSize};
+
+use super::{Color, Difficulty, LayerConnection, Puzzle, PuzzleCell, MAX_PUZZLE_SIDE};
+
+const VERSION: u8 = 1;
+
+const KIND_MISSING: u8 = 0;
+const KIND_NORMAL: u8 = 1;
+const KIND_SOURCE: u8 = 2;
+const KIND_INTERSECTION: u8 = 3;
+
+const FLAG_SEED: u8 = 1;
+const FLAG_DIFFICULTY: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleCodeError {
+    NotBase64,
+    UnsupportedVersion(u8),
+    UnsupportedFlags,
+    UnexpectedEnd,
+    TooLarge,
+    InvalidCell(GridIndex),
+    InvalidDifficulty,
+    HintOutsideGrid,
+    TrailingData,
+}
+
+impl fmt::Display for PuzzleCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleCodeError::NotBase64 => f.write_str("the code contains invalid characters"),
+            PuzzleCodeError::UnsupportedVersion(version) => {
+                write!(f, "the code uses an unsupported format version ({version})")
+            }
+            PuzzleCodeError::UnsupportedFlags => {
+                f.write_str("the code uses features this version does not support")
+            }
+            PuzzleCodeError::UnexpectedEnd => f.write_str("the code is incomplete"),
+            PuzzleCodeError::TooLarge => f.write_str("the puzzle in the code is too large"),
+            PuzzleCodeError::InvalidCell(index) => {
+                write!(f, "the cell at ({}, {}) is invalid", index.x, index.y)
+            }
+            PuzzleCodeError::InvalidDifficulty => f.write_str("the difficulty is invalid"),
+            PuzzleCodeError::HintOutsideGrid => f.write_str("the hint is outside the grid"),
+            PuzzleCodeError::TrailingData => f.write_str("the code has extra data at the end"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleCodeError {}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+const fn layer_bits(layer: LayerConnection) -> u8 {
+    match layer {
+        LayerConnection::None => 0,
+        LayerConnection::Layer0 => 1,
+        LayerConnection::Layer1 => 2,
+    }
+}
+
+fn encode_cell(bytes: &mut Vec<u8>, cell: Option<&PuzzleCell>) {
+    match cell {
+        None => bytes.push(KIND_MISSING << 6),
+        Some(PuzzleCell::Normal { connections }) => {
+            bytes.push(KIND_NORMAL << 6 | connections.bits());
+        }
+        Some(PuzzleCell::Source {
+            connections,
+            source,
+        }) => {
+            bytes.push(KIND_SOURCE << 6 | connections.bits());
+            bytes.push(source.bit());
+        }
+        Some(PuzzleCell::Intersection { connections }) => {
+            bytes.push(KIND_INTERSECTION << 6);
+            let layers = Direction::ALL.iter().enumerate().fold(0, |bits, (i, dir)| {
+                bits | layer_bits(connections[dir]) << (2 * i)
+            });
+            bytes.push(layers);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, PuzzleCodeError> {
+        let (first, rest) = self
+            .bytes
+            .split_first()
+            .ok_or(PuzzleCodeError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(*first)
+    }
+
+    fn varint(&mut self) -> Result<usize, PuzzleCodeError> {
+        let mut value = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(PuzzleCodeError::TooLarge)
+    }
+
+    fn u64(&mut self) -> Result<u64, PuzzleCodeError> {
+        let mut le = [0; 8];
+        for byte in le.iter_mut() {
+            *byte = self.byte()?;
+        }
+        Ok(u64::from_le_bytes(le))
+    }
+
+    fn cell(&mut self, index: GridIndex) -> Result<Option<PuzzleCell>, PuzzleCodeError> {
+        let invalid = PuzzleCodeError::InvalidCell(index);
+        let first = self.byte()?;
+        let low = first & 0x3f;
+        match first >> 6 {
+            KIND_MISSING if low == 0 => Ok(None),
+            KIND_NORMAL if low < 0x10 => Ok(Some(PuzzleCell::Normal {
+                connections: DirectionSet::from_bits(low),
+            })),
+            KIND_SOURCE if low < 0x10 => {
+                let source = *Color::ALL.get(self.byte()? as usize).ok_or(invalid)?;
+                Ok(Some(PuzzleCell::Source {
+                    connections: DirectionSet::from_bits(low),
+                    source,
+                }))
+            }
+            KIND_INTERSECTION if low == 0 => {
+                let layers = self.byte()?;
+                let mut connections = DirectionMap::new_with_repeat(LayerConnection::None);
+                for (i, dir) in Direction::ALL.iter().enumerate() {
+                    connections[dir] = match layers >> (2 * i) & 0b11 {
+                        0 => LayerConnection::None,
+                        1 => LayerConnection::Layer0,
+                        2 => LayerConnection::Layer1,
+                        _ => return Err(invalid),
+                    };
+                }
+                Ok(Some(PuzzleCell::Intersection { connections }))
+            }
+            _ => Err(invalid),
+        }
+    }
+}
+
+impl Puzzle {
+    /// Encodes this puzzle as a short code that can be pasted into chat or a URL.
+    pub fn to_code(&self) -> String {
+        let grid = self.start();
+        let size = grid.size();
+        let mut bytes = vec![VERSION];
+        write_varint(&mut bytes, size.width);
+        write_varint(&mut bytes, size.height);
+        write_varint(&mut bytes, self.hint().x);
+        write_varint(&mut bytes, self.hint().y);
+        bytes.push(self.swap_limit());
+
+        let mut flags = 0;
+        if self.seed().is_some() {
+            flags |= FLAG_SEED;
+        }
+        if self.difficulty().is_some() {
+            flags |= FLAG_DIFFICULTY;
+        }
+        bytes.push(flags);
+        if let Some(seed) = self.seed() {
+            bytes.extend_from_slice(&seed.to_le_bytes());
+        }
+        if let Some(difficulty) = self.difficulty() {
+            bytes.push(difficulty as u8);
+        }
+
+        for index in size {
+            encode_cell(&mut bytes, grid.get(index));
+        }
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /// Decodes a code made by [`Puzzle::to_code`]. Surrounding whitespace is ignored.
+    pub fn from_code(code: &str) -> Result<Puzzle, PuzzleCodeError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(code.trim())
+            .map_err(|_| PuzzleCodeError::NotBase64)?;
+        let mut reader = Reader { bytes: &bytes };
+
+        let version = reader.byte()?;
+        if version != VERSION {
+            return Err(PuzzleCodeError::UnsupportedVersion(version));
+        }
+        let width = reader.varint()?;
+        let height = reader.varint()?;
+        if width > MAX_PUZZLE_SIDE || height > MAX_PUZZLE_SIDE {
+            return Err(PuzzleCodeError::TooLarge);
+        }
+        let hint = GridIndex::new(reader.varint()?, reader.varint()?);
+        let swaps = reader.byte()?;
+
+        let flags = reader.byte()?;
+        if flags & !(FLAG_SEED | FLAG_DIFFICULTY) != 0 {
+            return Err(PuzzleCodeError::UnsupportedFlags);
+        }
+        let seed = match flags & FLAG_SEED {
+            0 => None,
+            _ => Some(reader.u64()?),
+        };
+        let difficulty = match flags & FLAG_DIFFICULTY {
+            0 => None,
+            _ => Some(
+                *Difficulty::ALL
+                    .get(reader.byte()? as usize)
+                    .ok_or(PuzzleCodeError::InvalidDifficulty)?,
+            ),
+        };
+
+        let size = GridSize::new(width, height);
+        if !size.contains(hint) {
+            return Err(PuzzleCodeError::HintOutsideGrid);
+        }
+        let mut grid = Grid::with_size(size);
+        for index in size {
+            if let Some(cell) = reader.cell(index)? {
+                grid.insert(index, cell)
+                    .expect("index is within the grid size");
+            }
+        }
+        if !reader.bytes.is_empty() {
+            return Err(PuzzleCodeError::TrailingData);
+        }
+
+        let mut puzzle = Puzzle::new(grid, swaps, hint);
+        if let Some(seed) = seed {
+            puzzle = puzzle.with_seed(seed);
+        }
+        if let Some(difficulty) = difficulty {
+            puzzle = puzzle.with_difficulty(difficulty);
+        }
+        Ok(puzzle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::debug_puzzle;
+
+    fn bytes_of(puzzle: &Puzzle) -> Vec<u8> {
+        URL_SAFE_NO_PAD.decode(puzzle.to_code()).unwrap()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Puzzle, PuzzleCodeError> {
+        Puzzle::from_code(&URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for puzzle in [debug_puzzle::test_puzzle(), debug_puzzle::debug_puzzle()] {
+            assert_eq!(Puzzle::from_code(&puzzle.to_code()), Ok(puzzle.clone()));
+            for difficulty in Difficulty::ALL {
+                let puzzle = puzzle
+                    .clone()
+                    .with_seed(u64::MAX - 7)
+                    .with_difficulty(difficulty);
+                assert_eq!(Puzzle::from_code(&puzzle.to_code()), Ok(puzzle));
+            }
+        }
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_ignored() {
+        let puzzle = debug_puzzle::test_puzzle();
+        let code = format!("  {}\n", puzzle.to_code());
+        assert_eq!(Puzzle::from_code(&code), Ok(puzzle));
+    }
+
+    #[test]
+    fn truncated_codes_are_rejected() {
+        let puzzle = debug_puzzle::debug_puzzle()
+            .with_seed(1234)
+            .with_difficulty(Difficulty::Hard);
+        let bytes = bytes_of(&puzzle);
+        for len in 0..bytes.len() {
+            assert_eq!(
+                from_bytes(&bytes[..len]),
+                Err(PuzzleCodeError::UnexpectedEnd),
+                "truncated to {len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        assert_eq!(
+            Puzzle::from_code("not a code!"),
+            Err(PuzzleCodeError::NotBase64)
+        );
+        assert_eq!(Puzzle::from_code(""), Err(PuzzleCodeError::UnexpectedEnd));
+        assert_eq!(
+            from_bytes(&[VERSION + 1]),
+            Err(PuzzleCodeError::UnsupportedVersion(VERSION + 1))
+        );
+
+        let mut bytes = bytes_of(&debug_puzzle::test_puzzle());
+        bytes.push(0);
+        assert_eq!(from_bytes(&bytes), Err(PuzzleCodeError::TrailingData));
+
+        // The flags byte follows the version, four single-byte varints and the swaps.
+        let mut bytes = bytes_of(&debug_puzzle::test_puzzle());
+        bytes[6] = 0x80;
+        assert_eq!(from_bytes(&bytes), Err(PuzzleCodeError::UnsupportedFlags));
+
+        // The first cell follows the flags.
+        let mut bytes = bytes_of(&debug_puzzle::test_puzzle());
+        bytes[7] = KIND_NORMAL << 6 | 0x3f;
+        assert_eq!(
+            from_bytes(&bytes),
+            Err(PuzzleCodeError::InvalidCell(GridIndex::new(0, 0)))
+        );
+    }
+
+    #[test]
+    fn oversized_dimensions_are_rejected() {
+        for (width, height) in [(MAX_PUZZLE_SIDE + 1, 1), (1, MAX_PUZZLE_SIDE + 1)] {
+            let mut bytes = vec![VERSION];
+            write_varint(&mut bytes, width);
+            write_varint(&mut bytes, height);
+            assert_eq!(from_bytes(&bytes), Err(PuzzleCodeError::TooLarge));
+        }
+
+        // A varint longer than a usize.
+        let mut bytes = vec![VERSION];
+        bytes.extend([0xff; 10]);
+        bytes.push(0x01);
+        assert_eq!(from_bytes(&bytes), Err(PuzzleCodeError::TooLarge));
+    }
+
+    #[test]
+    fn hint_outside_the_grid_is_rejected() {
+        let mut bytes = vec![VERSION];
+        for value in [1, 1, 1, 0] {
+            write_varint(&mut bytes, value);
+        }
+        bytes.extend([1, 0]);
+        assert_eq!(from_bytes(&bytes), Err(PuzzleCodeError::HintOutsideGrid));
+    }
+}
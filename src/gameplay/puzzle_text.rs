@@ -0,0 +1,440 @@
+//! Plain-text puzzle format.
+//!
+//! ```text
+//! # Lines starting with '#' are comments.
+//! swaps: 2
+//! hint: 1 0
+//! seed: 1234
+//! difficulty: Easy
+//! grid:
+//! E@p  EW  +E0N1W0S1
+//! .    N   -
+//! ```
+//!
+//! `swaps`, `hint` and `grid` are required; `seed` and `difficulty` are optional.
+//! Each row after `grid:` holds one whitespace-separated token per cell:
+//!
+//! - `.` is a missing cell.
+//! - `-` is a cell with no connections.
+//! - Direction letters in `ENWS` order are a normal cell with those connections.
+//! - A trailing `@` and color letter (`roygbp`) makes the cell a source, e.g. `ES@p`.
+//! - A leading `+` is an intersection, with a layer digit after each direction, e.g. `+E0N1W0S1`.
+
+use std::{fmt, str::FromStr};
+
+use crate::grids::{Direction, DirectionMap, DirectionSet, Grid, GridIndex, GridSize};
+
+use super::{Color, Difficulty, LayerConnection, Puzzle, PuzzleCell};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleTextError {
+    MissingField(&'static str),
+    UnknownField { line: usize, field: String },
+    InvalidValue { line: usize, field: &'static str },
+    InvalidToken { line: usize, token: String },
+    RaggedRow { line: usize },
+    EmptyGrid,
+    HintOutsideGrid,
+}
+
+impl fmt::Display for PuzzleTextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleTextError::MissingField(field) => write!(f, "missing `{field}` line"),
+            PuzzleTextError::UnknownField { line, field } => {
+                write!(f, "line {line}: unknown field `{field}`")
+            }
+            PuzzleTextError::InvalidValue { line, field } => {
+                write!(f, "line {line}: invalid value for `{field}`")
+            }
+            PuzzleTextError::InvalidToken { line, token } => {
+                write!(f, "line {line}: invalid cell `{token}`")
+            }
+            PuzzleTextError::RaggedRow { line } => {
+                write!(f, "line {line}: row has a different number of cells")
+            }
+            PuzzleTextError::EmptyGrid => f.write_str("grid has no rows"),
+            PuzzleTextError::HintOutsideGrid => f.write_str("hint is outside the grid"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleTextError {}
+
+const fn direction_char(direction: Direction) -> char {
+    match direction {
+        Direction::E => 'E',
+        Direction::N => 'N',
+        Direction::W => 'W',
+        Direction::S => 'S',
+    }
+}
+
+const fn color_char(color: Color) -> char {
+    match color {
+        Color::Red => 'r',
+        Color::Orange => 'o',
+        Color::Yellow => 'y',
+        Color::Green => 'g',
+        Color::Blue => 'b',
+        Color::Purple => 'p',
+    }
+}
+
+fn direction_for_char(c: char) -> Option<Direction> {
+    Direction::ALL.into_iter().find(|d| direction_char(*d) == c)
+}
+
+fn color_for_char(c: char) -> Option<Color> {
+    Color::ALL.into_iter().find(|color| color_char(*color) == c)
+}
+
+fn directions_token(connections: DirectionSet) -> String {
+    connections
+        .iter()
+        .filter(|(_, connected)| **connected)
+        .map(|(direction, _)| direction_char(direction))
+        .collect()
+}
+
+fn cell_token(cell: Option<&PuzzleCell>) -> String {
+    match cell {
+        None => ".".to_owned(),
+        Some(PuzzleCell::Normal { connections }) if connections.is_empty() => "-".to_owned(),
+        Some(PuzzleCell::Normal { connections }) => directions_token(*connections),
+        Some(PuzzleCell::Source {
+            connections,
+            source,
+        }) => format!("{}@{}", directions_token(*connections), color_char(*source)),
+        Some(PuzzleCell::Intersection { connections }) => {
+            let mut token = "+".to_owned();
+            for (direction, layer) in connections.iter() {
+                let digit = match layer {
+                    LayerConnection::None => continue,
+                    LayerConnection::Layer0 => '0',
+                    LayerConnection::Layer1 => '1',
+                };
+                token.push(direction_char(direction));
+                token.push(digit);
+            }
+            token
+        }
+    }
+}
+
+fn parse_directions(text: &str) -> Option<DirectionSet> {
+    let mut connections = DirectionSet::empty();
+    for c in text.chars() {
+        if connections.insert(direction_for_char(c)?) {
+            return None;
+        }
+    }
+    Some(connections)
+}
+
+fn parse_cell(token: &str) -> Option<Option<PuzzleCell>> {
+    if token == "." {
+        return Some(None);
+    }
+    if token == "-" {
+        return Some(Some(PuzzleCell::Normal {
+            connections: DirectionSet::empty(),
+        }));
+    }
+    if let Some(layers) = token.strip_prefix('+') {
+        let mut connections = DirectionMap::new_with_repeat(LayerConnection::None);
+        let mut chars = layers.chars();
+        while let Some(c) = chars.next() {
+            let direction = direction_for_char(c)?;
+            if connections[direction] != LayerConnection::None {
+                return None;
+            }
+            connections[direction] = match chars.next()? {
+                '0' => LayerConnection::Layer0,
+                '1' => LayerConnection::Layer1,
+                _ => return None,
+            };
+        }
+        return Some(Some(PuzzleCell::Intersection { connections }));
+    }
+    if let Some((directions, color)) = token.split_once('@') {
+        let mut color = color.chars();
+        let source = color.next().and_then(color_for_char)?;
+        if color.next().is_some() {
+            return None;
+        }
+        return Some(Some(PuzzleCell::Source {
+            connections: parse_directions(directions)?,
+            source,
+        }));
+    }
+    Some(Some(PuzzleCell::Normal {
+        connections: parse_directions(token)?,
+    }))
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "swaps: {}", self.swap_limit())?;
+        writeln!(f, "hint: {} {}", self.hint().x, self.hint().y)?;
+        if let Some(seed) = self.seed() {
+            writeln!(f, "seed: {seed}")?;
+        }
+        if let Some(difficulty) = self.difficulty() {
+            writeln!(f, "difficulty: {}", difficulty.name())?;
+        }
+        writeln!(f, "grid:")?;
+
+        let grid = self.start();
+        let size = grid.size();
+        let tokens: Vec<String> = size
+            .into_iter()
+            .map(|index| cell_token(grid.get(index)))
+            .collect();
+        let column_widths: Vec<usize> = (0..size.width)
+            .map(|x| {
+                (0..size.height)
+                    .map(|y| tokens[x + y * size.width].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for row in tokens.chunks(size.width.max(1)) {
+            let line = row
+                .iter()
+                .zip(column_widths.iter())
+                .map(|(token, width)| format!("{token:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = PuzzleTextError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut swaps = None;
+        let mut hint = None;
+        let mut seed = None;
+        let mut difficulty = None;
+        let mut rows: Option<Vec<(usize, Vec<&str>)>> = None;
+
+        for (line_index, line) in s.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(rows) = rows.as_mut() {
+                rows.push((line_number, line.split_whitespace().collect()));
+                continue;
+            }
+
+            let (field, value) = line.split_once(':').ok_or(PuzzleTextError::InvalidToken {
+                line: line_number,
+                token: line.to_owned(),
+            })?;
+            let value = value.trim();
+            let invalid = |field| PuzzleTextError::InvalidValue {
+                line: line_number,
+                field,
+            };
+            match field.trim() {
+                "swaps" => swaps = Some(value.parse::<u8>().map_err(|_| invalid("swaps"))?),
+                "hint" => {
+                    let mut parts = value.split_whitespace().map(str::parse::<usize>);
+                    hint = match (parts.next(), parts.next(), parts.next()) {
+                        (Some(Ok(x)), Some(Ok(y)), None) => Some(GridIndex::new(x, y)),
+                        _ => return Err(invalid("hint")),
+                    };
+                }
+                "seed" => seed = Some(value.parse::<u64>().map_err(|_| invalid("seed"))?),
+                "difficulty" => {
+                    difficulty = Some(
+                        Difficulty::ALL
+                            .into_iter()
+                            .find(|d| d.name().eq_ignore_ascii_case(value))
+                            .ok_or(invalid("difficulty"))?,
+                    )
+                }
+                "grid" if value.is_empty() => rows = Some(Vec::new()),
+                other => {
+                    return Err(PuzzleTextError::UnknownField {
+                        line: line_number,
+                        field: other.to_owned(),
+                    })
+                }
+            }
+        }
+
+        let swaps = swaps.ok_or(PuzzleTextError::MissingField("swaps"))?;
+        let hint = hint.ok_or(PuzzleTextError::MissingField("hint"))?;
+        let rows = rows.ok_or(PuzzleTextError::MissingField("grid"))?;
+        let width = rows.first().ok_or(PuzzleTextError::EmptyGrid)?.1.len();
+
+        let mut grid = Grid::with_size(GridSize::new(width, rows.len()));
+        for (y, (line, tokens)) in rows.iter().enumerate() {
+            if tokens.len() != width {
+                return Err(PuzzleTextError::RaggedRow { line: *line });
+            }
+            for (x, token) in tokens.iter().enumerate() {
+                let cell = parse_cell(token).ok_or_else(|| PuzzleTextError::InvalidToken {
+                    line: *line,
+                    token: (*token).to_owned(),
+                })?;
+                if let Some(cell) = cell {
+                    grid.insert(GridIndex::new(x, y), cell)
+                        .expect("row and column are within the grid size");
+                }
+            }
+        }
+        if !grid.size().contains(hint) {
+            return Err(PuzzleTextError::HintOutsideGrid);
+        }
+
+        let mut puzzle = Puzzle::new(grid, swaps, hint);
+        if let Some(seed) = seed {
+            puzzle = puzzle.with_seed(seed);
+        }
+        if let Some(difficulty) = difficulty {
+            puzzle = puzzle.with_difficulty(difficulty);
+        }
+        Ok(puzzle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::debug_puzzle;
+
+    #[test]
+    fn text_round_trips() {
+        for puzzle in [debug_puzzle::test_puzzle(), debug_puzzle::debug_puzzle()] {
+            assert_eq!(puzzle.to_string().parse(), Ok(puzzle.clone()));
+            let puzzle = puzzle.with_seed(1234).with_difficulty(Difficulty::Medium);
+            assert_eq!(puzzle.to_string().parse(), Ok(puzzle));
+        }
+    }
+
+    #[test]
+    fn fixtures_are_written_as_they_are_read() {
+        assert_eq!(
+            debug_puzzle::debug_puzzle().to_string(),
+            include_str!("puzzles/debug_puzzle.txt")
+        );
+        assert_eq!(
+            debug_puzzle::test_puzzle().to_string(),
+            include_str!("puzzles/test_puzzle.txt")
+        );
+    }
+
+    #[test]
+    fn example_parses() {
+        let text = "\
+# Lines starting with '#' are comments.
+swaps: 2
+hint: 1 0
+seed: 1234
+difficulty: Easy
+grid:
+E@p  EW  +E0N1W0S1
+.    N   -
+";
+        let puzzle: Puzzle = text.parse().unwrap();
+        assert_eq!(puzzle.swap_limit(), 2);
+        assert_eq!(puzzle.hint(), GridIndex::new(1, 0));
+        assert_eq!(puzzle.seed(), Some(1234));
+        assert_eq!(puzzle.difficulty(), Some(Difficulty::Easy));
+
+        let grid = puzzle.start();
+        assert_eq!(grid.size(), GridSize::new(3, 2));
+        assert_eq!(
+            grid.get(GridIndex::new(0, 0)),
+            Some(&PuzzleCell::Source {
+                connections: parse_directions("E").unwrap(),
+                source: Color::Purple,
+            })
+        );
+        assert_eq!(grid.get(GridIndex::new(0, 1)), None);
+        assert_eq!(
+            grid.get(GridIndex::new(2, 1)),
+            Some(&PuzzleCell::Normal {
+                connections: DirectionSet::empty(),
+            })
+        );
+        let Some(PuzzleCell::Intersection { connections }) = grid.get(GridIndex::new(2, 0)) else {
+            panic!("expected an intersection");
+        };
+        assert_eq!(connections[Direction::E], LayerConnection::Layer0);
+        assert_eq!(connections[Direction::N], LayerConnection::Layer1);
+    }
+
+    #[test]
+    fn missing_fields_are_rejected() {
+        let parse = |text: &str| text.parse::<Puzzle>();
+        assert_eq!(
+            parse("hint: 0 0\ngrid:\nE@p W\n"),
+            Err(PuzzleTextError::MissingField("swaps"))
+        );
+        assert_eq!(
+            parse("swaps: 1\ngrid:\nE@p W\n"),
+            Err(PuzzleTextError::MissingField("hint"))
+        );
+        assert_eq!(
+            parse("swaps: 1\nhint: 0 0\n"),
+            Err(PuzzleTextError::MissingField("grid"))
+        );
+        assert_eq!(
+            parse("swaps: 1\nhint: 0 0\ngrid:\n# no rows\n"),
+            Err(PuzzleTextError::EmptyGrid)
+        );
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected() {
+        let parse = |header: &str, rows: &str| format!("{header}grid:\n{rows}").parse::<Puzzle>();
+        let header = "swaps: 1\nhint: 0 0\n";
+        assert_eq!(
+            parse("swaps: 1\nhint: 0 0\ncolor: red\n", "E@p W\n"),
+            Err(PuzzleTextError::UnknownField {
+                line: 3,
+                field: "color".to_owned()
+            })
+        );
+        for (line, field) in [
+            ("swaps: 256", "swaps"),
+            ("hint: 0", "hint"),
+            ("hint: 0 0 0", "hint"),
+            ("seed: -1", "seed"),
+            ("difficulty: Impossible", "difficulty"),
+        ] {
+            assert_eq!(
+                parse(&format!("{header}{line}\n"), "E@p W\n"),
+                Err(PuzzleTextError::InvalidValue { line: 3, field }),
+                "{line}"
+            );
+        }
+        for token in ["EE", "X", "E@", "E@x", "E@pp", "+E2", "+E0E1", "+E"] {
+            assert_eq!(
+                parse(header, &format!("E@p {token}\n")),
+                Err(PuzzleTextError::InvalidToken {
+                    line: 4,
+                    token: token.to_owned()
+                }),
+                "{token}"
+            );
+        }
+        assert_eq!(
+            parse(header, "E@p W\nN\n"),
+            Err(PuzzleTextError::RaggedRow { line: 5 })
+        );
+        assert_eq!(
+            parse("swaps: 1\nhint: 2 0\n", "E@p W\n"),
+            Err(PuzzleTextError::HintOutsideGrid)
+        );
+    }
+}
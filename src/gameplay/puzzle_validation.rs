@@ -0,0 +1,264 @@
+use std::fmt;
+
+use crate::grids::{Direction, DirectionSet, Grid, GridIndex, Rotation};
+
+use super::{Color, Puzzle, PuzzleCell};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleError {
+    MalformedGrid,
+    ConnectionOffGrid(GridIndex, Direction),
+    ConnectionToMissingCell(GridIndex, Direction),
+    HintNotACell(GridIndex),
+    NoSwapSource,
+    OddConnectionCount,
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::MalformedGrid => f.write_str("the grid data does not match its size"),
+            PuzzleError::ConnectionOffGrid(index, direction) => write!(
+                f,
+                "({}, {}): connection {direction:?} points off the grid",
+                index.x, index.y
+            ),
+            PuzzleError::ConnectionToMissingCell(index, direction) => write!(
+                f,
+                "({}, {}): connection {direction:?} points into a missing cell",
+                index.x, index.y
+            ),
+            PuzzleError::HintNotACell(index) => {
+                write!(f, "({}, {}): the hint is not on a cell", index.x, index.y)
+            }
+            PuzzleError::NoSwapSource => f.write_str("there is no swap source"),
+            PuzzleError::OddConnectionCount => f.write_str(
+                "the tiles have an odd number of connections, so one can never be matched",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+impl PuzzleError {
+    /// Joins a list of errors from [`Puzzle::validate`] into one message.
+    pub fn describe_all(errors: &[PuzzleError]) -> String {
+        errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+impl Puzzle {
+    /// Checks that the puzzle can be played, reporting every problem found.
+    ///
+    /// The start grid is scrambled, so a tile may point off the grid or into a missing cell
+    /// until it is swapped into place. Stoppers can never be swapped, so their connections
+    /// are checked where they are. Every other tile only has to fit somewhere on the grid,
+    /// in any rotation the rotators could give it; one that fits nowhere is reported at its
+    /// place in the start grid.
+    ///
+    /// Every connection of a solved grid meets another one, so the tiles must have an even
+    /// number of connections between them. Layers are not checked on their own: a crossing
+    /// may end a path under another, leaving a layer with a single connection.
+    pub fn validate(&self) -> Result<(), Vec<PuzzleError>> {
+        let grid = self.start();
+        if !grid.is_consistent() {
+            return Err(vec![PuzzleError::MalformedGrid]);
+        }
+        let mut errors = Vec::new();
+
+        let rotations: &[Rotation] = if grid
+            .iter()
+            .any(|(_, cell)| matches!(cell.source(), Some(Color::CW | Color::CCW)))
+        {
+            &[
+                Rotation::None,
+                Rotation::CounterClockwise,
+                Rotation::Half,
+                Rotation::Clockwise,
+            ]
+        } else {
+            &[Rotation::None]
+        };
+        // Only the directions a tile connects in decide where it fits.
+        let mut fits_somewhere = [None; 16];
+        let mut connections = 0;
+        for (index, cell) in grid.iter() {
+            let directions = cell_directions(cell);
+            connections += cell.total_connections();
+            let fixed = cell.source() == Some(Color::STOP);
+            let fits = !fixed
+                && *fits_somewhere[directions.bits() as usize].get_or_insert_with(|| {
+                    rotations.iter().any(|&rotation| {
+                        grid.indicies()
+                            .any(|at| fits_at(&grid, directions.rotated(rotation), at))
+                    })
+                });
+            if fits {
+                continue;
+            }
+            for direction in directions.iter_set() {
+                match index.moved_in(direction) {
+                    Some(neighbor) if grid.contains(neighbor) => {}
+                    Some(neighbor) if grid.size().contains(neighbor) => {
+                        errors.push(PuzzleError::ConnectionToMissingCell(index, direction))
+                    }
+                    _ => errors.push(PuzzleError::ConnectionOffGrid(index, direction)),
+                }
+            }
+        }
+        if connections % 2 == 1 {
+            errors.push(PuzzleError::OddConnectionCount);
+        }
+
+        if !grid.contains(self.hint()) {
+            errors.push(PuzzleError::HintNotACell(self.hint()));
+        }
+        if !grid
+            .iter()
+            .any(|(_, cell)| cell.source() == Some(Color::SWAP))
+        {
+            errors.push(PuzzleError::NoSwapSource);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn cell_directions(cell: &PuzzleCell) -> DirectionSet {
+    let mut directions = DirectionSet::empty();
+    for layer in cell.iter_layers() {
+        for direction in layer.iter_set() {
+            directions.insert(direction);
+        }
+    }
+    directions
+}
+
+fn fits_at(grid: &Grid<PuzzleCell>, directions: DirectionSet, at: GridIndex) -> bool {
+    directions.iter_set().all(|direction| {
+        at.moved_in(direction)
+            .is_some_and(|neighbor| grid.contains(neighbor))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(text: &str) -> Result<(), Vec<PuzzleError>> {
+        text.parse::<Puzzle>().unwrap().validate()
+    }
+
+    #[test]
+    fn playable_puzzles_are_valid() {
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nE@p W E@p W\n"),
+            Ok(())
+        );
+        // Scrambled tiles may point off the grid until they are swapped into place.
+        assert_eq!(validate("swaps: 1\nhint: 0 0\ngrid:\nW@p E\n"), Ok(()));
+        // Rotators can turn a tile until it fits.
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nE@p W NS E@y W\n"),
+            Ok(())
+        );
+        // A crossing may end a path under another.
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nE@p +E0W1 W\n"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn malformed_grids_are_rejected() {
+        let puzzle: Puzzle = "swaps: 1\nhint: 0 0\ngrid:\nE@p W\n".parse().unwrap();
+        let mut value = serde_json::to_value(&puzzle).unwrap();
+        value["grid"]["filled"] = 3.into();
+        let puzzle: Puzzle = serde_json::from_value(value).unwrap();
+        assert_eq!(puzzle.validate(), Err(vec![PuzzleError::MalformedGrid]));
+    }
+
+    #[test]
+    fn connections_off_the_grid_are_found() {
+        // The stopper cannot move, and no place on one row fits the `S` tile.
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nE@p W N@r S\n"),
+            Err(vec![
+                PuzzleError::ConnectionOffGrid(GridIndex::new(2, 0), Direction::N),
+                PuzzleError::ConnectionOffGrid(GridIndex::new(3, 0), Direction::S),
+            ])
+        );
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nE@p W NS E@p W\n"),
+            Err(vec![
+                PuzzleError::ConnectionOffGrid(GridIndex::new(2, 0), Direction::N),
+                PuzzleError::ConnectionOffGrid(GridIndex::new(2, 0), Direction::S),
+            ])
+        );
+    }
+
+    #[test]
+    fn connections_to_missing_cells_are_found() {
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nES@p W\n. N\n"),
+            Err(vec![PuzzleError::ConnectionToMissingCell(
+                GridIndex::new(0, 0),
+                Direction::S
+            )])
+        );
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nE@p W S@r\nE@p - .\n"),
+            Err(vec![PuzzleError::ConnectionToMissingCell(
+                GridIndex::new(2, 0),
+                Direction::S
+            )])
+        );
+    }
+
+    #[test]
+    fn odd_connection_counts_are_found() {
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nE@p W E\n"),
+            Err(vec![PuzzleError::OddConnectionCount])
+        );
+    }
+
+    #[test]
+    fn hints_on_missing_cells_are_found() {
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 1\ngrid:\nE@p W\n. -\n"),
+            Err(vec![PuzzleError::HintNotACell(GridIndex::new(0, 1))])
+        );
+    }
+
+    #[test]
+    fn puzzles_without_a_swap_source_are_found() {
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 0\ngrid:\nE W\n"),
+            Err(vec![PuzzleError::NoSwapSource])
+        );
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        assert_eq!(
+            validate("swaps: 1\nhint: 0 1\ngrid:\nES N@r\n. -\n"),
+            Err(vec![
+                PuzzleError::ConnectionToMissingCell(GridIndex::new(0, 0), Direction::S),
+                PuzzleError::ConnectionOffGrid(GridIndex::new(1, 0), Direction::N),
+                PuzzleError::OddConnectionCount,
+                PuzzleError::HintNotACell(GridIndex::new(0, 1)),
+                PuzzleError::NoSwapSource,
+            ])
+        );
+    }
+}
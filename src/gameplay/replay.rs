@@ -0,0 +1,421 @@
+//! Recorded attempts at a puzzle.
+//!
+//! ```text
+//! # Lines starting with '#' are comments.
+//! puzzle: AQQEAAEC...
+//! 0.00 B2>-C3
+//! 1.52 undo
+//! 2.10 redo B2>-C3
+//! ```
+//!
+//! After the `puzzle:` line, which holds a puzzle code, each line is an event: the time in
+//! seconds since the attempt started, then a swap in move notation, `undo`, or `redo` and
+//! the swap it made again. Times never go backwards.
+
+use std::{fmt, str::FromStr};
+
+use super::{PlayingPuzzle, Puzzle, PuzzleError, SwapRecord};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayAction {
+    Swap(SwapRecord),
+    Undo,
+    Redo(SwapRecord),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayEvent {
+    pub time: f32,
+    pub action: ReplayAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    MissingPuzzle,
+    InvalidPuzzle(String),
+    InvalidEvent {
+        line: usize,
+    },
+    OutOfOrder {
+        line: usize,
+    },
+    /// The event numbered `event`, counting from 1, cannot be played on the puzzle.
+    Diverged {
+        event: usize,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::MissingPuzzle => f.write_str("missing `puzzle` line"),
+            ReplayError::InvalidPuzzle(err) => write!(f, "invalid puzzle: {err}"),
+            ReplayError::InvalidEvent { line } => write!(f, "line {line}: invalid event"),
+            ReplayError::OutOfOrder { line } => {
+                write!(f, "line {line}: event is earlier than the one before it")
+            }
+            ReplayError::Diverged { event } => {
+                write!(f, "event {event} cannot be played on the puzzle")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// A puzzle and everything the player did on it, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    puzzle: Puzzle,
+    events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    /// Starts recording from the current position of `puzzle`. Swaps already made are
+    /// recorded at time zero.
+    pub fn new(puzzle: &PlayingPuzzle) -> Self {
+        let moves = puzzle.moves();
+        let mut events: Vec<ReplayEvent> = moves
+            .ancestors(moves.current())
+            .map(|id| ReplayEvent {
+                time: 0.0,
+                action: ReplayAction::Swap(moves.get(id).expect("ids are in the tree").record),
+            })
+            .collect();
+        events.reverse();
+        Self {
+            puzzle: puzzle.puzzle().clone(),
+            events,
+        }
+    }
+
+    /// A replay that makes `swaps` from the start of `puzzle`, one every `interval` seconds.
+    pub fn from_swaps(puzzle: Puzzle, swaps: &[SwapRecord], interval: f32) -> Self {
+        let events = swaps
+            .iter()
+            .enumerate()
+            .map(|(i, record)| ReplayEvent {
+                time: (i + 1) as f32 * interval,
+                action: ReplayAction::Swap(*record),
+            })
+            .collect();
+        Self { puzzle, events }
+    }
+
+    pub const fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    pub fn events(&self) -> &[ReplayEvent] {
+        &self.events
+    }
+
+    /// The time of the last event.
+    pub fn duration(&self) -> f32 {
+        self.events.last().map_or(0.0, |event| event.time)
+    }
+
+    /// Records an action at `time`, clamped so events stay in order.
+    pub fn record(&mut self, time: f32, action: ReplayAction) {
+        let time = time.max(self.duration());
+        self.events.push(ReplayEvent { time, action });
+    }
+
+    /// Checks that the puzzle is valid and every event can be played on it.
+    pub fn validate(&self) -> Result<(), ReplayError> {
+        self.puzzle
+            .validate()
+            .map_err(|errors| ReplayError::InvalidPuzzle(PuzzleError::describe_all(&errors)))?;
+        let mut player = ReplayPlayer::new(self.clone());
+        while !player.is_finished() {
+            player.step()?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReplayAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayAction::Swap(record) => write!(f, "{record}"),
+            ReplayAction::Undo => f.write_str("undo"),
+            ReplayAction::Redo(record) => write!(f, "redo {record}"),
+        }
+    }
+}
+
+fn parse_action(text: &str) -> Option<ReplayAction> {
+    match text.trim() {
+        "undo" => Some(ReplayAction::Undo),
+        text => match text.strip_prefix("redo ") {
+            Some(record) => record.parse().ok().map(ReplayAction::Redo),
+            None => text.parse().ok().map(ReplayAction::Swap),
+        },
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "puzzle: {}", self.puzzle.to_code())?;
+        for event in self.events.iter() {
+            writeln!(f, "{:.2} {}", event.time, event.action)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = ReplayError;
+
+    /// Parses a replay. The events are not checked against the puzzle; see
+    /// [`Replay::validate`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut puzzle = None;
+        let mut events: Vec<ReplayEvent> = Vec::new();
+        for (line_index, line) in s.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if puzzle.is_none() {
+                let code = line
+                    .strip_prefix("puzzle:")
+                    .ok_or(ReplayError::MissingPuzzle)?;
+                puzzle = Some(
+                    Puzzle::from_code(code)
+                        .map_err(|err| ReplayError::InvalidPuzzle(err.to_string()))?,
+                );
+                continue;
+            }
+
+            let invalid = ReplayError::InvalidEvent { line: line_number };
+            let (time, action) = line
+                .split_once(char::is_whitespace)
+                .ok_or(invalid.clone())?;
+            let time: f32 = time.parse().map_err(|_| invalid.clone())?;
+            if !time.is_finite() || time < 0.0 {
+                return Err(invalid);
+            }
+            if events.last().is_some_and(|last| last.time > time) {
+                return Err(ReplayError::OutOfOrder { line: line_number });
+            }
+            let action = parse_action(action).ok_or(invalid)?;
+            events.push(ReplayEvent { time, action });
+        }
+        Ok(Self {
+            puzzle: puzzle.ok_or(ReplayError::MissingPuzzle)?,
+            events,
+        })
+    }
+}
+
+/// Plays a replay back on its own copy of the puzzle.
+pub struct ReplayPlayer {
+    replay: Replay,
+    puzzle: PlayingPuzzle,
+    next: usize,
+    time: f32,
+}
+
+impl ReplayPlayer {
+    /// Waits between events are cut to this many seconds.
+    const MAX_PAUSE: f32 = 2.0;
+
+    pub fn new(replay: Replay) -> Self {
+        let puzzle = PlayingPuzzle::play(replay.puzzle.clone());
+        Self {
+            replay,
+            puzzle,
+            next: 0,
+            time: 0.0,
+        }
+    }
+
+    pub const fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub const fn puzzle(&self) -> &PlayingPuzzle {
+        &self.puzzle
+    }
+
+    /// How many events have been played.
+    pub const fn position(&self) -> usize {
+        self.next
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.events.len()
+    }
+
+    /// Plays the next event, returning the swap applied to the grid, inverted for an undo.
+    pub fn step(&mut self) -> Result<Option<SwapRecord>, ReplayError> {
+        let Some(event) = self.replay.events.get(self.next).copied() else {
+            return Ok(None);
+        };
+        self.next += 1;
+        self.time = self.time.max(event.time);
+        let diverged = ReplayError::Diverged { event: self.next };
+        let applied = match event.action {
+            ReplayAction::Swap(record) | ReplayAction::Redo(record) => {
+                // Making a swap again from the same position returns to its branch, so a
+                // redo plays out the same whichever branch redo would have followed.
+                let made = self.puzzle.try_swap(record.a(), record.b());
+                if made != Some(record) {
+                    if made.is_some() {
+                        self.puzzle.try_undo();
+                    }
+                    return Err(diverged);
+                }
+                record
+            }
+            ReplayAction::Undo => self.puzzle.try_undo().ok_or(diverged)?.inverse(),
+        };
+        Ok(Some(applied))
+    }
+
+    /// Moves the playback clock forward and plays every event that is due.
+    pub fn advance(&mut self, dt: f32) -> Result<Vec<SwapRecord>, ReplayError> {
+        let mut applied = Vec::new();
+        if let Some(next) = self.replay.events.get(self.next) {
+            self.time = (self.time + dt).max(next.time - Self::MAX_PAUSE);
+        }
+        while self
+            .replay
+            .events
+            .get(self.next)
+            .is_some_and(|next| next.time <= self.time)
+        {
+            applied.extend(self.step()?);
+        }
+        Ok(applied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gameplay::debug_puzzle,
+        grids::{GridIndex, Rotation},
+    };
+
+    fn swap(a: (usize, usize), b: (usize, usize)) -> SwapRecord {
+        SwapRecord::new(
+            GridIndex::new(a.0, a.1),
+            GridIndex::new(b.0, b.1),
+            Rotation::None,
+            Rotation::Clockwise,
+        )
+    }
+
+    fn replay_text(events: &str) -> String {
+        format!(
+            "puzzle: {}\n{events}",
+            debug_puzzle::debug_puzzle().to_code()
+        )
+    }
+
+    #[test]
+    fn replays_round_trip() {
+        let mut replay = Replay::from_swaps(
+            debug_puzzle::debug_puzzle(),
+            &[swap((1, 0), (2, 0)), swap((1, 1), (2, 1))],
+            0.25,
+        );
+        replay.record(1.5, ReplayAction::Undo);
+        replay.record(2.75, ReplayAction::Redo(swap((1, 1), (2, 1))));
+        assert_eq!(replay.to_string().parse(), Ok(replay));
+    }
+
+    #[test]
+    fn example_parses() {
+        let text = replay_text(
+            "\
+# Lines starting with '#' are comments.
+0.00 B2>-C3
+
+1.52 undo
+  2.10   redo B2>-C3
+",
+        );
+        let replay: Replay = text.parse().unwrap();
+        assert_eq!(replay.puzzle(), &debug_puzzle::debug_puzzle());
+        let record = SwapRecord::new(
+            GridIndex::new(1, 1),
+            GridIndex::new(2, 2),
+            Rotation::Clockwise,
+            Rotation::None,
+        );
+        assert_eq!(
+            replay.events(),
+            [
+                ReplayEvent {
+                    time: 0.0,
+                    action: ReplayAction::Swap(record)
+                },
+                ReplayEvent {
+                    time: 1.52,
+                    action: ReplayAction::Undo
+                },
+                ReplayEvent {
+                    time: 2.1,
+                    action: ReplayAction::Redo(record)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_or_invalid_puzzles_are_rejected() {
+        let parse = |text: &str| text.parse::<Replay>();
+        assert_eq!(parse(""), Err(ReplayError::MissingPuzzle));
+        assert_eq!(parse("# only a comment\n"), Err(ReplayError::MissingPuzzle));
+        assert_eq!(parse("0.00 undo\n"), Err(ReplayError::MissingPuzzle));
+        assert!(matches!(
+            parse("puzzle: !!\n"),
+            Err(ReplayError::InvalidPuzzle(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_events_are_rejected() {
+        let parse = |events: &str| replay_text(events).parse::<Replay>();
+        for event in [
+            "undo",
+            "x undo",
+            "-1 undo",
+            "NaN undo",
+            "inf undo",
+            "0.00 jump",
+            "0.00 B2",
+            "0.00 redo",
+            "0.00 redo undo",
+        ] {
+            assert_eq!(
+                parse(&format!("{event}\n")),
+                Err(ReplayError::InvalidEvent { line: 2 }),
+                "{event}"
+            );
+        }
+        assert_eq!(
+            parse("1.00 undo\n0.50 undo\n"),
+            Err(ReplayError::OutOfOrder { line: 3 })
+        );
+    }
+
+    #[test]
+    fn events_that_cannot_be_played_are_found_by_validate() {
+        let puzzle: Puzzle = "swaps: 1\nhint: 0 0\ngrid:\nE@p W E@p W\n".parse().unwrap();
+        let record = PlayingPuzzle::play(puzzle.clone())
+            .try_swap(GridIndex::new(1, 0), GridIndex::new(2, 0))
+            .unwrap();
+        let replay = Replay::from_swaps(puzzle.clone(), &[record], 1.0);
+        assert_eq!(replay.validate(), Ok(()));
+
+        let text = format!("puzzle: {}\n0.00 undo\n", puzzle.to_code());
+        let replay: Replay = text.parse().unwrap();
+        assert_eq!(replay.validate(), Err(ReplayError::Diverged { event: 1 }));
+    }
+}
@@ -17,4 +17,30 @@ impl SwapRecord {
             b_rotation,
         }
     }
+
+    /// The swap that takes the cells back, e.g. to replay an undo.
+    pub const fn inverse(self) -> Self {
+        Self {
+            a: self.a,
+            b: self.b,
+            a_rotation: self.b_rotation.inverse(),
+            b_rotation: self.a_rotation.inverse(),
+        }
+    }
+
+    pub const fn a(&self) -> GridIndex {
+        self.a
+    }
+
+    pub const fn b(&self) -> GridIndex {
+        self.b
+    }
+
+    pub const fn a_rotation(&self) -> Rotation {
+        self.a_rotation
+    }
+
+    pub const fn b_rotation(&self) -> Rotation {
+        self.b_rotation
+    }
 }
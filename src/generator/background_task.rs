@@ -0,0 +1,163 @@
+/// Work that can be advanced a little at a time, such as a puzzle generation or a solution
+/// search.
+pub(crate) trait Job {
+    type Output;
+
+    /// Does a small amount of work, returning the output once the job is finished.
+    fn step(&mut self) -> Option<Self::Output>;
+
+    /// A count of the work done so far, for showing progress.
+    fn progress(&self) -> usize {
+        0
+    }
+}
+
+/// A [`Job`] running off the UI thread.
+///
+/// Natively this runs on a worker thread. On the web, the job is advanced in short
+/// slices each time the task is polled. Dropping the task cancels it.
+pub(crate) struct BackgroundTask<J: Job> {
+    #[cfg(not(target_arch = "wasm32"))]
+    inner: thread_task::ThreadTask<J>,
+    #[cfg(target_arch = "wasm32")]
+    inner: sliced_task::SlicedTask<J>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<J> BackgroundTask<J>
+where
+    J: Job + Send + 'static,
+    J::Output: Send,
+{
+    pub fn start(job: J) -> Self {
+        Self {
+            inner: thread_task::ThreadTask::start(job),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<J: Job> BackgroundTask<J> {
+    pub fn start(job: J) -> Self {
+        Self {
+            inner: sliced_task::SlicedTask::start(job),
+        }
+    }
+}
+
+impl<J: Job> BackgroundTask<J> {
+    /// The progress of the job when it last finished a step.
+    pub fn progress(&self) -> usize {
+        self.inner.progress()
+    }
+
+    /// Returns the output once the job has finished.
+    pub fn poll(&mut self) -> Option<J::Output> {
+        self.inner.poll()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod thread_task {
+    use std::sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    };
+
+    use super::Job;
+
+    pub struct ThreadTask<J: Job> {
+        progress: Arc<AtomicUsize>,
+        cancelled: Arc<AtomicBool>,
+        receiver: Receiver<J::Output>,
+    }
+
+    impl<J: Job> ThreadTask<J> {
+        pub fn start(mut job: J) -> Self
+        where
+            J: Send + 'static,
+            J::Output: Send,
+        {
+            let progress = Arc::new(AtomicUsize::new(0));
+            let cancelled = Arc::new(AtomicBool::new(false));
+            let (sender, receiver) = mpsc::channel();
+
+            let thread_progress = progress.clone();
+            let thread_cancelled = cancelled.clone();
+            std::thread::spawn(move || {
+                while !thread_cancelled.load(Ordering::Relaxed) {
+                    let output = job.step();
+                    thread_progress.store(job.progress(), Ordering::Relaxed);
+                    if let Some(output) = output {
+                        _ = sender.send(output);
+                        return;
+                    }
+                }
+            });
+
+            Self {
+                progress,
+                cancelled,
+                receiver,
+            }
+        }
+
+        pub fn progress(&self) -> usize {
+            self.progress.load(Ordering::Relaxed)
+        }
+
+        pub fn poll(&mut self) -> Option<J::Output> {
+            self.receiver.try_recv().ok()
+        }
+    }
+
+    impl<J: Job> Drop for ThreadTask<J> {
+        fn drop(&mut self) {
+            self.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod sliced_task {
+    use std::time::Duration;
+
+    use web_time::Instant;
+
+    use super::Job;
+
+    pub struct SlicedTask<J> {
+        job: Option<J>,
+        progress: usize,
+    }
+
+    impl<J: Job> SlicedTask<J> {
+        const SLICE: Duration = Duration::from_millis(8);
+
+        pub fn start(job: J) -> Self {
+            Self {
+                job: Some(job),
+                progress: 0,
+            }
+        }
+
+        pub fn progress(&self) -> usize {
+            self.progress
+        }
+
+        pub fn poll(&mut self) -> Option<J::Output> {
+            let job = self.job.as_mut()?;
+            let start = Instant::now();
+            while start.elapsed() < Self::SLICE {
+                let output = job.step();
+                self.progress = job.progress();
+                if output.is_some() {
+                    self.job = None;
+                    return output;
+                }
+            }
+            None
+        }
+    }
+}
@@ -1,7 +1,7 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
-use egui::ahash::{HashMap, HashMapExt, HashSet};
-use rand::seq::IteratorRandom;
+use egui::ahash::{HashMap, HashMapExt};
+use rand::{seq::IteratorRandom, Rng};
 
 use crate::{
     gameplay::{Color, LayerConnection, PuzzleCell},
@@ -19,7 +19,7 @@ enum ConnectionCell {
     Intersection(DirectionMap<LayerConnection>, [Color; 2]),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 struct CxnGridIndex {
     grid_index: GridIndex,
     layer_index: Option<usize>,
@@ -119,6 +119,7 @@ fn get_layer(grid: &ConnectionGrid, index: CxnGridIndex) -> DirectionSet {
 pub fn connect_groups(
     gen_grid: Grid<GeneratorCell>,
     generator_settings: &GeneratorSettings,
+    rng: &mut impl Rng,
 ) -> Result<Grid<PuzzleCell>, GeneratorFailure> {
     let mut grid = ConnectionGrid::with_size(gen_grid.size());
     let mut intersections_left = generator_settings.max_intersections;
@@ -143,7 +144,7 @@ pub fn connect_groups(
 
     let mut present_colors = Vec::new();
     for color in Color::ALL {
-        let indices: HashSet<_> = get_all_indices(&grid, color).collect();
+        let indices: BTreeSet<_> = get_all_indices(&grid, color).collect();
         if !indices.is_empty() {
             present_colors.push(color);
         } else {
@@ -175,6 +176,7 @@ pub fn connect_groups(
             *color,
             &mut intersections_left,
             generator_settings,
+            rng,
         );
     }
 
@@ -187,12 +189,12 @@ pub fn connect_groups(
             return Err(GeneratorFailure::CannotAddSource);
         }
 
-        let source = potential_sources.swap_remove(random_index(potential_sources.len()));
+        let source = potential_sources.swap_remove(random_index(rng, potential_sources.len()));
         all_sources.insert(source.grid_index, *color);
-        while !potential_sources.is_empty() && chance(generator_settings.extra_source_chance) {
+        while !potential_sources.is_empty() && chance(rng, generator_settings.extra_source_chance) {
             all_sources.insert(
                 potential_sources
-                    .swap_remove(random_index(potential_sources.len()))
+                    .swap_remove(random_index(rng, potential_sources.len()))
                     .grid_index,
                 *color,
             );
@@ -230,23 +232,24 @@ fn knockout_loops(
     color: Color,
     intersections_left: &mut usize,
     generator_settings: &GeneratorSettings,
+    rng: &mut impl Rng,
 ) {
     loop {
-        if !chance(generator_settings.knockout_loop_chance) {
+        if !chance(rng, generator_settings.knockout_loop_chance) {
             break;
         }
 
-        let indices: HashSet<_> = get_all_indices(grid, color).collect();
+        let indices: BTreeSet<_> = get_all_indices(grid, color).collect();
         if indices.len() <= 3 {
             break;
         }
 
-        let origin = *indices.iter().choose(&mut rand::thread_rng()).unwrap();
+        let origin = *indices.iter().choose(rng).unwrap();
         let mut not_checked = indices;
 
-        let mut counts = HashMap::new();
+        let mut counts = BTreeMap::new();
         counts.insert(origin, 1);
-        let mut prevs = HashMap::new();
+        let mut prevs = BTreeMap::new();
         let mut to_explore = VecDeque::new();
         to_explore.push_back(origin);
 
@@ -273,10 +276,10 @@ fn knockout_loops(
                         && get_layer(grid, neighbor_index).contains(neighbor.1.inverse())
                     {
                         match counts.entry(neighbor_index) {
-                            std::collections::hash_map::Entry::Occupied(mut entry) => {
+                            std::collections::btree_map::Entry::Occupied(mut entry) => {
                                 *entry.get_mut() += 1
                             }
-                            std::collections::hash_map::Entry::Vacant(entry) => {
+                            std::collections::btree_map::Entry::Vacant(entry) => {
                                 entry.insert(1);
                             }
                         }
@@ -291,11 +294,11 @@ fn knockout_loops(
             .iter()
             .filter(|(_, count)| **count > 1)
             .filter_map(|(p, _)| prevs.get(p).map(|prev| (*p, *prev)))
-            .choose(&mut rand::thread_rng())
+            .choose(rng)
         {
             if break_to.layer_index.is_none()
                 && *intersections_left > 0
-                && chance(generator_settings.intersection_chance)
+                && chance(rng, generator_settings.intersection_chance)
             {
                 *intersections_left -= 1;
                 let layer = get_layer(grid, break_to);
@@ -318,10 +321,10 @@ fn knockout_loops(
     }
 }
 
-fn random_index(length: usize) -> usize {
-    rand::random::<usize>() % length
+fn random_index(rng: &mut impl Rng, length: usize) -> usize {
+    rng.gen_range(0..length as u32) as usize
 }
 
-fn chance(c: f32) -> bool {
-    rand::random::<f32>() < c
+fn chance(rng: &mut impl Rng, c: f32) -> bool {
+    rng.gen::<f32>() < c
 }
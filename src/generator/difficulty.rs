@@ -0,0 +1,257 @@
+use crate::{
+    gameplay::{Cell, Color, Difficulty, GameGrid, Puzzle, PuzzleCell, SwapRecord},
+    grids::Grid,
+};
+
+use super::solver::{get_possible_swaps, CountJob, SolveJob, SolverLimits};
+
+/// Measurements of how hard a puzzle is to solve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PuzzleDifficulty {
+    pub swap_limit: u8,
+    // Fewest swaps that solve the puzzle, if a solution was found within the limits.
+    pub minimum_swaps: Option<u8>,
+    // Average number of possible swaps at each position along the shortest solution.
+    pub branching_factor: f32,
+    // Number of distinct solutions within the swap limit, if they could be counted.
+    pub solutions: Option<usize>,
+    pub has_stoppers: bool,
+    pub has_rotators: bool,
+    pub has_intersections: bool,
+}
+
+impl PuzzleDifficulty {
+    const SOLUTION_CAP: usize = 10;
+    const LIMITS: SolverLimits = SolverLimits {
+        max_nodes: Some(50_000),
+        max_duration: None,
+    };
+
+    /// Measures a puzzle by searching for a shortest solution and counting its solutions.
+    pub fn estimate(puzzle: &Puzzle) -> Self {
+        DifficultyJob::estimate(puzzle).run()
+    }
+
+    /// Measures a puzzle whose solutions were already counted, if they were. `shortest` is
+    /// used when it is known to be a shortest solution; otherwise one is searched for.
+    pub fn with_solutions(
+        puzzle: &Puzzle,
+        shortest: Option<&[SwapRecord]>,
+        solutions: Option<usize>,
+    ) -> Self {
+        match shortest {
+            Some(solution) => Self::measure(puzzle, Some(solution), solutions),
+            None => DifficultyJob::with_solutions(puzzle, solutions).run(),
+        }
+    }
+
+    fn measure(puzzle: &Puzzle, solution: Option<&[SwapRecord]>, solutions: Option<usize>) -> Self {
+        let start = puzzle.start();
+        let has_source = |colors: &[Color]| {
+            start
+                .iter()
+                .any(|(_, cell)| cell.source().is_some_and(|source| colors.contains(&source)))
+        };
+        let has_stoppers = has_source(&[Color::STOP]);
+        let has_rotators = has_source(&[Color::CCW, Color::CW]);
+        let has_intersections = start
+            .iter()
+            .any(|(_, cell)| matches!(cell, PuzzleCell::Intersection { connections: _ }));
+
+        let mut grid: Grid<Cell> = Grid::from_puzzle_grid(start);
+        let mut branching = vec![get_possible_swaps(&grid).len()];
+        if let Some(solution) = solution {
+            for record in solution.iter().take(solution.len().saturating_sub(1)) {
+                grid.swap_with_rotation(record.a, record.b);
+                grid.fill_after_swap(record.a, record.b);
+                branching.push(get_possible_swaps(&grid).len());
+            }
+        }
+        let branching_factor = branching.iter().sum::<usize>() as f32 / branching.len() as f32;
+
+        Self {
+            swap_limit: puzzle.swap_limit(),
+            minimum_swaps: solution.map(|solution| solution.len() as u8),
+            branching_factor,
+            solutions,
+            has_stoppers,
+            has_rotators,
+            has_intersections,
+        }
+    }
+
+    /// A rough score, where higher is harder.
+    pub fn score(&self) -> f32 {
+        // A puzzle too large to search is assumed to need every swap and to have one solution.
+        let swaps = self.minimum_swaps.unwrap_or(self.swap_limit) as f32;
+        let solutions = self.solutions.unwrap_or(1).max(1) as f32;
+        let mut score = swaps * 2.0 + self.branching_factor.max(1.0).log2() - solutions.log2();
+        if self.has_stoppers {
+            score += 1.0;
+        }
+        if self.has_rotators {
+            score += 1.5;
+        }
+        if self.has_intersections {
+            score += 1.0;
+        }
+        score
+    }
+
+    pub fn label(&self) -> Difficulty {
+        let score = self.score();
+        if score < 8.0 {
+            Difficulty::Easy
+        } else if score < 12.0 {
+            Difficulty::Medium
+        } else {
+            Difficulty::Hard
+        }
+    }
+}
+
+/// The searches behind [`PuzzleDifficulty`], run a little at a time so that puzzle
+/// generation can be spread over frames.
+pub(super) struct DifficultyJob {
+    puzzle: Puzzle,
+    solutions: Option<usize>,
+    stage: DifficultyStage,
+}
+
+enum DifficultyStage {
+    Counting(CountJob),
+    Searching(SolveJob),
+}
+
+impl DifficultyJob {
+    /// Counts the solutions of `puzzle`, then searches for a shortest one.
+    pub fn estimate(puzzle: &Puzzle) -> Self {
+        let grid = Grid::from_puzzle_grid(puzzle.start());
+        Self {
+            puzzle: puzzle.clone(),
+            solutions: None,
+            stage: DifficultyStage::Counting(CountJob::new(
+                &grid,
+                puzzle.swap_limit(),
+                PuzzleDifficulty::SOLUTION_CAP,
+                PuzzleDifficulty::LIMITS,
+            )),
+        }
+    }
+
+    /// Searches for a shortest solution of a puzzle whose solutions were already counted.
+    pub fn with_solutions(puzzle: &Puzzle, solutions: Option<usize>) -> Self {
+        Self {
+            puzzle: puzzle.clone(),
+            solutions,
+            stage: Self::search(puzzle),
+        }
+    }
+
+    fn search(puzzle: &Puzzle) -> DifficultyStage {
+        let grid = Grid::from_puzzle_grid(puzzle.start());
+        DifficultyStage::Searching(SolveJob::new(
+            &grid,
+            puzzle.swap_limit(),
+            PuzzleDifficulty::LIMITS,
+        ))
+    }
+
+    /// Advances whichever search is running, returning the measurements once both are done.
+    pub fn step(&mut self) -> Option<PuzzleDifficulty> {
+        match &mut self.stage {
+            DifficultyStage::Counting(count) => {
+                self.solutions = count.step()?.ok();
+                self.stage = Self::search(&self.puzzle);
+                None
+            }
+            DifficultyStage::Searching(search) => {
+                let solution = search.step()?.ok().flatten();
+                Some(PuzzleDifficulty::measure(
+                    &self.puzzle,
+                    solution.as_deref(),
+                    self.solutions,
+                ))
+            }
+        }
+    }
+
+    fn run(mut self) -> PuzzleDifficulty {
+        loop {
+            if let Some(difficulty) = self.step() {
+                return difficulty;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn difficulty(minimum_swaps: u8) -> PuzzleDifficulty {
+        PuzzleDifficulty {
+            swap_limit: 6,
+            minimum_swaps: Some(minimum_swaps),
+            branching_factor: 1.0,
+            solutions: Some(1),
+            has_stoppers: false,
+            has_rotators: false,
+            has_intersections: false,
+        }
+    }
+
+    #[test]
+    fn scores_add_up() {
+        assert_eq!(difficulty(3).score(), 6.0);
+        let measured = PuzzleDifficulty {
+            branching_factor: 4.0,
+            solutions: Some(2),
+            has_stoppers: true,
+            has_rotators: true,
+            has_intersections: true,
+            ..difficulty(3)
+        };
+        assert_eq!(measured.score(), 6.0 + 2.0 - 1.0 + 1.0 + 1.5 + 1.0);
+        // Unknown measurements count as every swap and a single solution.
+        let unknown = PuzzleDifficulty {
+            minimum_swaps: None,
+            solutions: None,
+            ..difficulty(3)
+        };
+        assert_eq!(unknown.score(), 12.0);
+    }
+
+    #[test]
+    fn labels_change_at_eight_and_twelve() {
+        let with_rotators = |swaps| PuzzleDifficulty {
+            has_rotators: true,
+            ..difficulty(swaps)
+        };
+        assert_eq!(difficulty(1).label(), Difficulty::Easy);
+        assert_eq!(with_rotators(3).label(), Difficulty::Easy);
+        assert_eq!(difficulty(4).label(), Difficulty::Medium);
+        assert_eq!(with_rotators(5).label(), Difficulty::Medium);
+        assert_eq!(difficulty(6).label(), Difficulty::Hard);
+
+        let two_solutions = PuzzleDifficulty {
+            solutions: Some(2),
+            ..difficulty(4)
+        };
+        assert_eq!(two_solutions.label(), Difficulty::Easy);
+    }
+
+    #[test]
+    fn known_shortest_solutions_are_measured_without_searching() {
+        let puzzle: Puzzle = "swaps: 3\nhint: 0 0\ngrid:\nW E@p\n".parse().unwrap();
+        let measured = PuzzleDifficulty::with_solutions(&puzzle, None, Some(1));
+        assert_eq!(measured.minimum_swaps, Some(1));
+        assert_eq!(measured, PuzzleDifficulty::estimate(&puzzle));
+
+        let solution = get_possible_swaps(&Grid::from_puzzle_grid(puzzle.start()));
+        assert_eq!(
+            PuzzleDifficulty::with_solutions(&puzzle, Some(&solution), Some(1)),
+            measured
+        );
+    }
+}
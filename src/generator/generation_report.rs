@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use web_time::Instant;
+
+use crate::gameplay::MAX_PUZZLE_SIDE;
+
+use super::{solutions::GeneratorFailure, GeneratorSettings};
+
+/// Limits on how much work puzzle generation may do before giving up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerationBudget {
+    pub max_attempts: Option<usize>,
+    pub max_duration: Option<Duration>,
+}
+
+impl GenerationBudget {
+    pub(crate) fn start(&self) -> BudgetTracker {
+        BudgetTracker {
+            max_attempts: self.max_attempts,
+            deadline: self.max_duration.map(|duration| Instant::now() + duration),
+        }
+    }
+}
+
+impl Default for GenerationBudget {
+    fn default() -> Self {
+        Self {
+            max_attempts: Some(1000),
+            max_duration: Some(Duration::from_secs(5)),
+        }
+    }
+}
+
+pub(crate) struct BudgetTracker {
+    max_attempts: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+impl BudgetTracker {
+    pub fn allows(&self, report: &GenerationReport) -> bool {
+        self.max_attempts
+            .map(|max| report.attempts < max)
+            .unwrap_or(true)
+            && !self.out_of_time()
+    }
+
+    /// Also checked at every step of an attempt, whose searches can take a while on
+    /// large grids.
+    pub fn out_of_time(&self) -> bool {
+        self.deadline
+            .map(|deadline| Instant::now() >= deadline)
+            .unwrap_or(false)
+    }
+}
+
+/// Counts of failed generation attempts, by the stage that failed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GenerationReport {
+    pub attempts: usize,
+    // Regions could not be grown to fill the grid.
+    pub allocate_failures: usize,
+    // A region had no cell that could hold a source.
+    pub source_failures: usize,
+    // The connected grid did not form a solved puzzle.
+    pub not_solved_failures: usize,
+    // No swap could be reversed from the solved grid.
+    pub reverse_failures: usize,
+    // The scrambled puzzle was already solved.
+    pub already_solved_failures: usize,
+    // The puzzle had more solutions than allowed.
+    pub too_many_solutions_failures: usize,
+    // The puzzle was not the requested difficulty.
+    pub difficulty_failures: usize,
+    // Times a shorter solution than intended was found and the puzzle was scrambled again.
+    pub shorter_solution_retries: usize,
+}
+
+impl GenerationReport {
+    pub(crate) fn record(&mut self, failure: GeneratorFailure) {
+        match failure {
+            GeneratorFailure::RandomFailure => self.allocate_failures += 1,
+            GeneratorFailure::CannotAddSource => self.source_failures += 1,
+            GeneratorFailure::ResultNotSolved => self.not_solved_failures += 1,
+            GeneratorFailure::NoReverseSolution => self.reverse_failures += 1,
+            GeneratorFailure::AlreadySolved => self.already_solved_failures += 1,
+            GeneratorFailure::TooManySolutions => self.too_many_solutions_failures += 1,
+            GeneratorFailure::WrongDifficulty => self.difficulty_failures += 1,
+            GeneratorFailure::OutOfTime => (),
+        }
+    }
+}
+
+impl std::ops::AddAssign for GenerationReport {
+    fn add_assign(&mut self, rhs: Self) {
+        self.attempts += rhs.attempts;
+        self.allocate_failures += rhs.allocate_failures;
+        self.source_failures += rhs.source_failures;
+        self.not_solved_failures += rhs.not_solved_failures;
+        self.reverse_failures += rhs.reverse_failures;
+        self.already_solved_failures += rhs.already_solved_failures;
+        self.too_many_solutions_failures += rhs.too_many_solutions_failures;
+        self.difficulty_failures += rhs.difficulty_failures;
+        self.shorter_solution_retries += rhs.shorter_solution_retries;
+    }
+}
+
+impl std::fmt::Display for GenerationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} attempts", self.attempts)?;
+        if self.shorter_solution_retries > 0 {
+            write!(
+                f,
+                ", {} retries after shorter solutions",
+                self.shorter_solution_retries
+            )?;
+        }
+        for (count, reason) in [
+            (self.allocate_failures, "could not allocate regions"),
+            (self.source_failures, "could not add a source"),
+            (self.not_solved_failures, "did not form a solution"),
+            (self.reverse_failures, "could not be scrambled"),
+            (self.already_solved_failures, "were already solved"),
+            (self.too_many_solutions_failures, "had too many solutions"),
+            (self.difficulty_failures, "were the wrong difficulty"),
+        ] {
+            if count > 0 {
+                write!(f, ", {count} {reason}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Settings that can never produce a puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsError {
+    SizeTooSmall,
+    SizeTooLarge,
+    NoSwaps,
+    TooManyMissing,
+    ChanceOutOfRange(&'static str),
+}
+
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::SizeTooSmall => f.write_str("the grid must have at least two cells"),
+            SettingsError::SizeTooLarge => write!(
+                f,
+                "the grid can be at most {MAX_PUZZLE_SIDE} cells wide and high"
+            ),
+            SettingsError::NoSwaps => f.write_str("the swap count must be at least one"),
+            SettingsError::TooManyMissing => {
+                f.write_str("too many missing cells for the size of the grid")
+            }
+            SettingsError::ChanceOutOfRange(name) => {
+                write!(f, "{name} must be between 0 and 1")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl GeneratorSettings {
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        if self.size.width > MAX_PUZZLE_SIDE || self.size.height > MAX_PUZZLE_SIDE {
+            return Err(SettingsError::SizeTooLarge);
+        }
+        let area = self.size.width * self.size.height;
+        if area <= 1 {
+            return Err(SettingsError::SizeTooSmall);
+        }
+        if self.swap_count == 0 {
+            return Err(SettingsError::NoSwaps);
+        }
+        if self.missing.saturating_add(2) > area {
+            return Err(SettingsError::TooManyMissing);
+        }
+        for (chance, name) in [
+            (self.missing_chance, "missing chance"),
+            (self.extra_region_chance, "extra region chance"),
+            (self.extra_source_chance, "extra source chance"),
+            (self.intersection_chance, "intersection chance"),
+            (self.knockout_loop_chance, "knockout loop chance"),
+        ] {
+            if !(0.0..=1.0).contains(&chance) {
+                return Err(SettingsError::ChanceOutOfRange(name));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationError {
+    InvalidSettings(SettingsError),
+    BudgetExhausted(GenerationReport),
+}
+
+impl std::fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerationError::InvalidSettings(err) => write!(f, "invalid settings: {err}"),
+            GenerationError::BudgetExhausted(report) => {
+                write!(f, "no puzzle found after {report}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerationError {}
+
+impl From<SettingsError> for GenerationError {
+    fn from(value: SettingsError) -> Self {
+        GenerationError::InvalidSettings(value)
+    }
+}
@@ -0,0 +1,50 @@
+use crate::gameplay::Puzzle;
+
+use super::{
+    background_task::{BackgroundTask, Job},
+    generator_methods::GenerationJob,
+    GenerationBudget, GenerationError, GeneratorSettings,
+};
+
+/// Puzzle generation running off the UI thread. Dropping the task cancels it.
+pub struct GenerationTask {
+    task: Result<BackgroundTask<GenerationJob>, Option<GenerationError>>,
+}
+
+impl GenerationTask {
+    pub fn start(settings: GeneratorSettings, budget: GenerationBudget) -> Self {
+        Self {
+            task: GenerationJob::new(settings, budget)
+                .map(BackgroundTask::start)
+                .map_err(Some),
+        }
+    }
+
+    /// Number of generation attempts made so far.
+    pub fn attempts(&self) -> usize {
+        self.task
+            .as_ref()
+            .map(BackgroundTask::progress)
+            .unwrap_or(0)
+    }
+
+    /// Returns the result once generation has finished.
+    pub fn poll(&mut self) -> Option<Result<Puzzle, GenerationError>> {
+        match &mut self.task {
+            Ok(task) => task.poll(),
+            Err(err) => err.take().map(Err),
+        }
+    }
+}
+
+impl Job for GenerationJob {
+    type Output = Result<Puzzle, GenerationError>;
+
+    fn step(&mut self) -> Option<Self::Output> {
+        GenerationJob::step(self)
+    }
+
+    fn progress(&self) -> usize {
+        self.report().attempts
+    }
+}
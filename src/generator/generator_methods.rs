@@ -1,11 +1,16 @@
 use crate::{
-    gameplay::{Cell, Color, GameGrid, GridSolveState, Puzzle, SwapRecord},
-    generator::solver::find_solution,
+    gameplay::{Cell, Color, Difficulty, GameGrid, GridSolveState, Puzzle, SwapRecord},
+    generator::solver::{CountJob, SolveJob, SolverLimits},
     grids::{Grid, GridIndex, GridSize, Rotation},
 };
 use rand::prelude::*;
 
-use super::solutions::generate_solution;
+use super::{
+    difficulty::{DifficultyJob, PuzzleDifficulty},
+    generation_report::{BudgetTracker, GenerationBudget, GenerationError, GenerationReport},
+    solutions::{generate_solution, GeneratorFailure},
+    GeneratorRng,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum SourceSettings {
@@ -14,7 +19,8 @@ pub enum SourceSettings {
     Definitely,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct GeneratorSettings {
     // Size of the grid.
     pub size: GridSize,
@@ -45,6 +51,15 @@ pub struct GeneratorSettings {
     pub check_solution_len: usize,
     // If solution check fails, how many times to we retry before giving up.
     pub check_solution_retries: usize,
+    // If non-zero, rejects puzzles with more than this many solutions.
+    #[serde(default)]
+    pub max_solutions: usize,
+    // If set, rejects puzzles of any other difficulty.
+    #[serde(default)]
+    pub target_difficulty: Option<Difficulty>,
+    // Seed for the random number generator. If None, a random seed is chosen.
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 impl Default for GeneratorSettings {
@@ -64,66 +79,302 @@ impl Default for GeneratorSettings {
             knockout_loop_chance: 0.33,
             check_solution_len: 1,
             check_solution_retries: 3,
+            max_solutions: 0,
+            target_difficulty: None,
+            seed: None,
         }
     }
 }
 
-pub fn generate_puzzle(generator_settings: &GeneratorSettings) -> Puzzle {
-    if generator_settings.size.width * generator_settings.size.height <= 1 {
-        panic!("Size too small!");
-    }
+pub fn try_generate_puzzle_with_budget(
+    generator_settings: &GeneratorSettings,
+    budget: GenerationBudget,
+) -> Result<Puzzle, GenerationError> {
+    let mut job = GenerationJob::new(generator_settings.clone(), budget)?;
     loop {
-        if let Some(puzzle) = try_generate_puzzle(generator_settings) {
-            break puzzle;
+        if let Some(result) = job.step() {
+            break result;
         }
     }
 }
 
-fn try_generate_puzzle(generator_settings: &GeneratorSettings) -> Option<Puzzle> {
-    let solution_grid = generate_solution(generator_settings);
-    let mut working_grid = Grid::from_puzzle_grid(solution_grid);
-
-    let mut solution = reverse_solution(&mut working_grid, generator_settings.swap_count);
-    if solution.is_empty() {
-        return None;
-    }
-    println!("initial {:?}", solution);
-
-    let mut puzzle = create_puzzle_from_grid(
-        &mut working_grid,
-        solution.len() as u8,
-        solution.first().copied().unwrap(),
-    );
-
-    let check = generator_settings
-        .check_solution_len
-        .min(generator_settings.swap_count as usize - 1);
-    for _ in 0..generator_settings.check_solution_retries {
-        if let Some(shorter_solution) = find_solution(&puzzle, check as u8) {
-            let remaining = reverse_solution(
-                &mut working_grid,
-                generator_settings.swap_count - shorter_solution.len() as u8,
-            );
-            solution = [remaining, shorter_solution].concat();
-            if solution.is_empty() {
-                return None;
+/// Puzzle generation that can be advanced a little at a time.
+pub struct GenerationJob {
+    settings: GeneratorSettings,
+    seed: u64,
+    rng: GeneratorRng,
+    tracker: BudgetTracker,
+    report: GenerationReport,
+    attempt: Option<Attempt>,
+}
+
+impl GenerationJob {
+    pub fn new(
+        settings: GeneratorSettings,
+        budget: GenerationBudget,
+    ) -> Result<Self, GenerationError> {
+        settings.validate()?;
+        let seed = settings.seed.unwrap_or_else(rand::random);
+        Ok(Self {
+            settings,
+            seed,
+            rng: GeneratorRng::seed_from_u64(seed),
+            tracker: budget.start(),
+            report: GenerationReport::default(),
+            attempt: None,
+        })
+    }
+
+    pub const fn report(&self) -> &GenerationReport {
+        &self.report
+    }
+
+    /// Starts a generation attempt, or advances the current one by a single step of its
+    /// searches, returning the result once generation is finished.
+    pub fn step(&mut self) -> Option<Result<Puzzle, GenerationError>> {
+        let result = match &mut self.attempt {
+            // Stopping early only ever abandons the attempt, so the puzzle a seed
+            // produces does not depend on where the deadline falls.
+            Some(_) if self.tracker.out_of_time() => Err(GeneratorFailure::OutOfTime),
+            Some(attempt) => attempt.step(&self.settings, &mut self.rng, &mut self.report),
+            None => {
+                if !self.tracker.allows(&self.report) {
+                    return Some(Err(GenerationError::BudgetExhausted(self.report)));
+                }
+                self.report.attempts += 1;
+                Attempt::start(&self.settings, &mut self.rng).map(|attempt| {
+                    self.attempt = Some(attempt);
+                    None
+                })
             }
-            puzzle = create_puzzle_from_grid(
-                &mut working_grid,
-                solution.len() as u8,
-                solution.first().copied().unwrap(),
-            );
-            println!("retry {:?}", solution);
-        } else {
-            break;
+        };
+        match result {
+            Ok(None) => None,
+            Ok(Some(puzzle)) => {
+                self.attempt = None;
+                Some(Ok(puzzle.with_seed(self.seed)))
+            }
+            Err(GeneratorFailure::OutOfTime) => {
+                Some(Err(GenerationError::BudgetExhausted(self.report)))
+            }
+            Err(failure) => {
+                self.attempt = None;
+                self.report.record(failure);
+                None
+            }
+        }
+    }
+}
+
+// Only a node limit, so that whether the check gives up, and so the puzzle a seed
+// produces, does not depend on the speed of the machine.
+const CHECK_SOLUTION_LIMITS: SolverLimits = SolverLimits {
+    max_nodes: Some(200_000),
+    max_duration: None,
+};
+
+// Counting stops early at `max_solutions + 1`, but a unique puzzle must be searched in
+// full, so it gets a budget of its own. Node-only for the same reason as above.
+const COUNT_SOLUTIONS_LIMITS: SolverLimits = SolverLimits {
+    max_nodes: Some(100_000),
+    max_duration: None,
+};
+
+/// A single generation attempt. The searches that check a scrambled puzzle can take a
+/// while on large grids, so they are advanced a step at a time, letting the web build
+/// hand control back to the browser part way through.
+struct Attempt {
+    working_grid: Grid<Cell>,
+    solution: Vec<SwapRecord>,
+    puzzle: Puzzle,
+    check: usize,
+    // Whether the check ruled out every solution shorter than the scramble.
+    scramble_is_shortest: bool,
+    solutions: Option<usize>,
+    stage: AttemptStage,
+}
+
+enum AttemptStage {
+    // Looking for a solution shorter than the scramble, which is scrambled again if
+    // one is found.
+    CheckingSolution {
+        search: SolveJob,
+        retries_left: usize,
+    },
+    // Done checking, with nothing searched yet.
+    Checked,
+    CountingSolutions(CountJob),
+    Labelling(DifficultyJob),
+}
+
+impl Attempt {
+    fn start(
+        generator_settings: &GeneratorSettings,
+        rng: &mut impl Rng,
+    ) -> Result<Self, GeneratorFailure> {
+        let solution_grid = generate_solution(generator_settings, rng)?;
+        let mut working_grid = Grid::from_puzzle_grid(solution_grid);
+
+        let solution = reverse_solution(&mut working_grid, generator_settings.swap_count, rng);
+        if solution.is_empty() {
+            return Err(GeneratorFailure::NoReverseSolution);
+        }
+        log::debug!("initial {:?}", solution);
+
+        let puzzle = create_puzzle_from_grid(
+            &mut working_grid,
+            solution.len() as u8,
+            solution.first().copied().unwrap(),
+        );
+        let check = generator_settings
+            .check_solution_len
+            .min((generator_settings.swap_count as usize).saturating_sub(1));
+        let stage = Self::check_solution(&puzzle, check, generator_settings.check_solution_retries);
+        Ok(Self {
+            working_grid,
+            solution,
+            puzzle,
+            check,
+            scramble_is_shortest: false,
+            solutions: None,
+            stage,
+        })
+    }
+
+    /// Advances the attempt, returning the puzzle once every check has passed.
+    fn step(
+        &mut self,
+        generator_settings: &GeneratorSettings,
+        rng: &mut impl Rng,
+        report: &mut GenerationReport,
+    ) -> Result<Option<Puzzle>, GeneratorFailure> {
+        match &mut self.stage {
+            AttemptStage::CheckingSolution {
+                search,
+                retries_left,
+            } => {
+                let retries_left = *retries_left;
+                let Some(checked) = search.step() else {
+                    return Ok(None);
+                };
+                // If the check gives up, keep the puzzle rather than spending more of the
+                // budget.
+                if let Ok(Some(shorter_solution)) = checked {
+                    let remaining = reverse_solution(
+                        &mut self.working_grid,
+                        generator_settings.swap_count - shorter_solution.len() as u8,
+                        rng,
+                    );
+                    self.solution = [remaining, shorter_solution].concat();
+                    if self.solution.is_empty() {
+                        return Err(GeneratorFailure::NoReverseSolution);
+                    }
+                    self.puzzle = create_puzzle_from_grid(
+                        &mut self.working_grid,
+                        self.solution.len() as u8,
+                        self.solution.first().copied().unwrap(),
+                    );
+                    report.shorter_solution_retries += 1;
+                    log::debug!("retry {:?}", self.solution);
+                    self.stage = Self::check_solution(&self.puzzle, self.check, retries_left);
+                } else {
+                    self.scramble_is_shortest =
+                        checked == Ok(None) && self.check + 1 >= self.solution.len();
+                    self.stage = AttemptStage::Checked;
+                }
+                Ok(None)
+            }
+            AttemptStage::Checked => {
+                let start = Grid::from_puzzle_grid(self.puzzle.start());
+                if start.is_solved() == GridSolveState::Solved {
+                    return Err(GeneratorFailure::AlreadySolved);
+                }
+                if generator_settings.max_solutions == 0 {
+                    return self.counted(generator_settings);
+                }
+                self.stage = AttemptStage::CountingSolutions(CountJob::new(
+                    &start,
+                    self.puzzle.swap_limit(),
+                    generator_settings.max_solutions + 1,
+                    COUNT_SOLUTIONS_LIMITS,
+                ));
+                Ok(None)
+            }
+            AttemptStage::CountingSolutions(count) => {
+                let Some(count) = count.step() else {
+                    return Ok(None);
+                };
+                // A puzzle that cannot be counted within the limits is not known to be
+                // unique enough.
+                match count {
+                    Ok(count) if count <= generator_settings.max_solutions => {
+                        self.solutions = Some(count);
+                    }
+                    _ => return Err(GeneratorFailure::TooManySolutions),
+                }
+                self.counted(generator_settings)
+            }
+            AttemptStage::Labelling(difficulty) => {
+                let Some(difficulty) = difficulty.step() else {
+                    return Ok(None);
+                };
+                self.labelled(generator_settings, difficulty)
+            }
+        }
+    }
+
+    /// The search for a solution shorter than the scramble, if any retries are left.
+    fn check_solution(puzzle: &Puzzle, check: usize, retries: usize) -> AttemptStage {
+        if retries == 0 {
+            return AttemptStage::Checked;
+        }
+        AttemptStage::CheckingSolution {
+            search: SolveJob::new(
+                &Grid::from_puzzle_grid(puzzle.start()),
+                check as u8,
+                CHECK_SOLUTION_LIMITS,
+            ),
+            retries_left: retries - 1,
         }
     }
 
-    if Grid::from_puzzle_grid(puzzle.start().clone()).is_solved() == GridSolveState::Solved {
-        return None;
+    fn counted(
+        &mut self,
+        generator_settings: &GeneratorSettings,
+    ) -> Result<Option<Puzzle>, GeneratorFailure> {
+        let job = match generator_settings.target_difficulty {
+            Some(_) => DifficultyJob::estimate(&self.puzzle),
+            // Without a known shortest solution the label has to search for one.
+            None if !self.scramble_is_shortest => {
+                DifficultyJob::with_solutions(&self.puzzle, self.solutions)
+            }
+            None => {
+                let difficulty = PuzzleDifficulty::with_solutions(
+                    &self.puzzle,
+                    Some(&self.solution),
+                    self.solutions,
+                );
+                return self.labelled(generator_settings, difficulty);
+            }
+        };
+        self.stage = AttemptStage::Labelling(job);
+        Ok(None)
     }
 
-    Some(puzzle)
+    fn labelled(
+        &self,
+        generator_settings: &GeneratorSettings,
+        difficulty: PuzzleDifficulty,
+    ) -> Result<Option<Puzzle>, GeneratorFailure> {
+        let difficulty = difficulty.label();
+        if generator_settings
+            .target_difficulty
+            .is_some_and(|target| target != difficulty)
+        {
+            return Err(GeneratorFailure::WrongDifficulty);
+        }
+        Ok(Some(self.puzzle.clone().with_difficulty(difficulty)))
+    }
 }
 
 fn create_puzzle_from_grid(
@@ -177,10 +428,10 @@ fn swap_is_trivial(grid: &Grid<Cell>, record: SwapRecord) -> bool {
     }
 }
 
-fn reverse_solution(grid: &mut Grid<Cell>, swaps: u8) -> Vec<SwapRecord> {
+fn reverse_solution(grid: &mut Grid<Cell>, swaps: u8, rng: &mut impl Rng) -> Vec<SwapRecord> {
     let mut solution = Vec::new();
     for _ in 0..swaps {
-        if let Some(record) = reverse_swap(grid) {
+        if let Some(record) = reverse_swap(grid, rng) {
             solution.push(record);
         } else {
             break;
@@ -190,7 +441,7 @@ fn reverse_solution(grid: &mut Grid<Cell>, swaps: u8) -> Vec<SwapRecord> {
     solution
 }
 
-fn reverse_swap(grid: &mut Grid<Cell>) -> Option<SwapRecord> {
+fn reverse_swap(grid: &mut Grid<Cell>, rng: &mut impl Rng) -> Option<SwapRecord> {
     let mut possible_rotations = vec![Rotation::None];
     if grid
         .iter_layers()
@@ -213,12 +464,12 @@ fn reverse_swap(grid: &mut Grid<Cell>) -> Option<SwapRecord> {
     let mut rotations: [Rotation; 2] = [Rotation::None, Rotation::None];
     for _ in 0..999 {
         positions
-            .choose_multiple(&mut rand::thread_rng(), selected.len())
+            .choose_multiple(rng, selected.len())
             .zip(selected.iter_mut())
             .for_each(|(s, buf)| *buf = *s);
         rotations
             .iter_mut()
-            .for_each(|buf| *buf = *possible_rotations.choose(&mut rand::thread_rng()).unwrap());
+            .for_each(|buf| *buf = *possible_rotations.choose(rng).unwrap());
 
         let record = SwapRecord::new(selected[0], selected[1], rotations[0], rotations[1]);
 
@@ -244,3 +495,119 @@ fn reverse_swap(grid: &mut Grid<Cell>) -> Option<SwapRecord> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gameplay::MAX_PUZZLE_SIDE, generator::SettingsError};
+
+    fn generate(settings: &GeneratorSettings) -> (Puzzle, GenerationReport) {
+        let budget = GenerationBudget {
+            max_attempts: Some(1000),
+            max_duration: None,
+        };
+        let mut job = GenerationJob::new(settings.clone(), budget).unwrap();
+        loop {
+            if let Some(result) = job.step() {
+                break (result.unwrap(), *job.report());
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_puzzle() {
+        let settings = GeneratorSettings {
+            seed: Some(1),
+            ..Default::default()
+        };
+        let (puzzle, _) = generate(&settings);
+        assert_eq!(puzzle.seed(), Some(1));
+        assert_eq!(puzzle, generate(&settings).0);
+
+        let other = GeneratorSettings {
+            seed: Some(2),
+            ..settings
+        };
+        assert_ne!(puzzle, generate(&other).0);
+    }
+
+    #[test]
+    fn solution_checks_do_not_depend_on_time() {
+        assert_eq!(CHECK_SOLUTION_LIMITS.max_duration, None);
+        assert_eq!(COUNT_SOLUTIONS_LIMITS.max_duration, None);
+        for seed in 0..8 {
+            let settings = GeneratorSettings {
+                size: GridSize::new(3, 3),
+                swap_count: 3,
+                check_solution_len: 2,
+                max_solutions: 2,
+                seed: Some(seed),
+                ..Default::default()
+            };
+            assert_eq!(generate(&settings), generate(&settings));
+        }
+    }
+
+    #[test]
+    fn oversized_settings_are_rejected() {
+        let settings = GeneratorSettings {
+            size: GridSize::new(MAX_PUZZLE_SIDE + 1, 3),
+            ..Default::default()
+        };
+        assert_eq!(settings.validate(), Err(SettingsError::SizeTooLarge));
+        let settings = GeneratorSettings {
+            size: GridSize::new(usize::MAX, usize::MAX),
+            ..Default::default()
+        };
+        assert_eq!(settings.validate(), Err(SettingsError::SizeTooLarge));
+        let settings = GeneratorSettings {
+            missing: usize::MAX,
+            ..Default::default()
+        };
+        assert_eq!(settings.validate(), Err(SettingsError::TooManyMissing));
+    }
+
+    #[test]
+    fn labels_come_from_a_shortest_solution() {
+        for check_solution_len in [0, 2] {
+            for seed in 0..4 {
+                let settings = GeneratorSettings {
+                    size: GridSize::new(3, 3),
+                    swap_count: 3,
+                    check_solution_len,
+                    seed: Some(seed),
+                    ..Default::default()
+                };
+                let (puzzle, _) = generate(&settings);
+                let measured = PuzzleDifficulty::with_solutions(&puzzle, None, None);
+                assert_eq!(puzzle.difficulty(), Some(measured.label()));
+            }
+        }
+    }
+
+    #[test]
+    fn attempts_are_spread_over_many_steps() {
+        let settings = GeneratorSettings {
+            swap_count: 4,
+            check_solution_len: 3,
+            seed: Some(0),
+            ..Default::default()
+        };
+        let budget = GenerationBudget {
+            max_attempts: Some(1000),
+            max_duration: None,
+        };
+        let mut job = GenerationJob::new(settings.clone(), budget).unwrap();
+        let mut steps_per_attempt = vec![];
+        let puzzle = loop {
+            let result = job.step();
+            steps_per_attempt.resize(job.report().attempts, 0);
+            *steps_per_attempt.last_mut().unwrap() += 1;
+            if let Some(result) = result {
+                break result.unwrap();
+            }
+        };
+        assert!(steps_per_attempt.iter().any(|&steps| steps > 100));
+        assert_eq!(puzzle, generate(&settings).0);
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use crate::gameplay::{PlayingPuzzle, PuzzleSolveState, SwapRecord};
+
+use super::{SolveTask, SolverError, SolverLimits};
+
+/// What the solver suggests from a position in a puzzle being played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    /// The first swap of a shortest solution from here.
+    Swap(SwapRecord),
+    Solved,
+    /// No solution fits in the swaps that are left.
+    NoSolution,
+    /// The search gave up before finding an answer.
+    GaveUp(SolverError),
+}
+
+impl Hint {
+    /// Limits that keep a hint search from running on for long after it stops being useful,
+    /// or from taking much memory while another search may be running.
+    pub const LIMITS: SolverLimits = SolverLimits {
+        max_nodes: Some(500_000),
+        max_duration: Some(Duration::from_secs(10)),
+    };
+}
+
+/// Looks for a hint from the current grid of a puzzle, within the swaps it has left.
+pub struct HintTask {
+    search: Result<SolveTask, Option<Hint>>,
+}
+
+impl HintTask {
+    pub fn start(puzzle: &PlayingPuzzle, limits: SolverLimits) -> Self {
+        let search = match puzzle.is_solved() {
+            PuzzleSolveState::Solved => Err(Some(Hint::Solved)),
+            PuzzleSolveState::TooManySwaps => Err(Some(Hint::NoSolution)),
+            _ => {
+                let swaps_left = puzzle.swap_limit().saturating_sub(puzzle.swaps_made());
+                let swaps_left = u8::try_from(swaps_left).unwrap_or(u8::MAX);
+                Ok(SolveTask::start(puzzle.grid(), swaps_left, limits))
+            }
+        };
+        Self { search }
+    }
+
+    /// Returns the hint once the search has finished.
+    pub fn poll(&mut self) -> Option<Hint> {
+        match &mut self.search {
+            Ok(task) => task.poll().map(|result| match result {
+                Ok(Some(solution)) => solution.first().copied().map_or(Hint::Solved, Hint::Swap),
+                Ok(None) => Hint::NoSolution,
+                Err(err) => Hint::GaveUp(err),
+            }),
+            Err(hint) => hint.take(),
+        }
+    }
+}
@@ -1,5 +1,23 @@
+mod background_task;
 mod connections;
+mod difficulty;
+mod generation_report;
+mod generation_task;
 mod generator_methods;
+mod hints;
 mod solutions;
+mod solve_task;
 mod solver;
-pub use generator_methods::{generate_puzzle, GeneratorSettings, SourceSettings};
+pub use difficulty::PuzzleDifficulty;
+pub use generation_report::{GenerationBudget, GenerationError, GenerationReport, SettingsError};
+pub use generation_task::GenerationTask;
+pub use generator_methods::{
+    try_generate_puzzle_with_budget, GenerationJob, GeneratorSettings, SourceSettings,
+};
+pub use hints::{Hint, HintTask};
+pub use solve_task::SolveTask;
+pub use solver::{count_solutions, find_solution, SolverError, SolverLimits};
+
+/// Portable random number generator used by every generator stage, so that a seed
+/// produces the same puzzle on every platform.
+pub type GeneratorRng = rand_chacha::ChaCha8Rng;
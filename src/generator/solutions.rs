@@ -1,5 +1,6 @@
-use egui::ahash::{HashSet, HashSetExt};
-use rand::seq::IteratorRandom;
+use std::collections::BTreeSet;
+
+use rand::{seq::IteratorRandom, Rng};
 
 use crate::{
     gameplay::{Color, GameGrid, GridSolveState, PuzzleCell},
@@ -9,15 +10,6 @@ use crate::{
 
 use super::GeneratorSettings;
 
-pub fn generate_solution(generator_settings: &GeneratorSettings) -> Grid<PuzzleCell> {
-    loop {
-        match try_generate_solution(generator_settings) {
-            Ok(solution) => break solution,
-            Err(err) => println!("{err:?} (retrying)"),
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum GeneratorCell {
     SingleGroup(Color),
@@ -29,29 +21,39 @@ pub(crate) enum GeneratorFailure {
     RandomFailure,
     ResultNotSolved,
     CannotAddSource,
+    NoReverseSolution,
+    AlreadySolved,
+    TooManySolutions,
+    WrongDifficulty,
+    // The generation budget ran out part way through an attempt.
+    OutOfTime,
 }
 
-fn try_generate_solution(
+pub(crate) fn generate_solution(
     generator_settings: &GeneratorSettings,
+    rng: &mut impl Rng,
 ) -> Result<Grid<PuzzleCell>, GeneratorFailure> {
-    let grid = create_grid_with_knockouts(generator_settings);
-    println!("grid created");
-    let grid = allocate_groups(grid, generator_settings)?;
-    println!("grid allocated");
-    let grid = connect_groups(grid, generator_settings)?;
-    println!("grid connected");
+    let grid = create_grid_with_knockouts(generator_settings, rng);
+    log::debug!("grid created");
+    let grid = allocate_groups(grid, generator_settings, rng)?;
+    log::debug!("grid allocated");
+    let grid = connect_groups(grid, generator_settings, rng)?;
+    log::debug!("grid connected");
     verify(grid)
 }
 
-fn create_grid_with_knockouts(generator_settings: &GeneratorSettings) -> Grid<()> {
+fn create_grid_with_knockouts(
+    generator_settings: &GeneratorSettings,
+    rng: &mut impl Rng,
+) -> Grid<()> {
     let mut grid = Grid::with_size(generator_settings.size);
     let mut positions: Vec<_> = grid.size().into_iter().collect();
 
     let mut knockouts = 0;
     while knockouts < generator_settings.missing && !positions.is_empty() {
-        if chance(generator_settings.missing_chance) {
+        if chance(rng, generator_settings.missing_chance) {
             knockouts += 1;
-            positions.swap_remove(random_index(positions.len()));
+            positions.swap_remove(random_index(rng, positions.len()));
         } else {
             break;
         }
@@ -73,8 +75,8 @@ enum GroupStatus {
 #[derive(Debug, Clone)]
 struct Group {
     color: Color,
-    present: HashSet<GridIndex>,
-    boundary: HashSet<GridIndex>,
+    present: BTreeSet<GridIndex>,
+    boundary: BTreeSet<GridIndex>,
     intersections: usize,
 }
 
@@ -82,8 +84,8 @@ impl Group {
     fn new(color: Color) -> Self {
         Self {
             color,
-            present: HashSet::new(),
-            boundary: HashSet::new(),
+            present: BTreeSet::new(),
+            boundary: BTreeSet::new(),
             intersections: 0,
         }
     }
@@ -106,8 +108,9 @@ impl Group {
 fn allocate_groups(
     grid: Grid<()>,
     generator_settings: &GeneratorSettings,
+    rng: &mut impl Rng,
 ) -> Result<Grid<GeneratorCell>, GeneratorFailure> {
-    fn get_group_colors(generator_settings: &GeneratorSettings) -> Vec<Color> {
+    fn get_group_colors(generator_settings: &GeneratorSettings, rng: &mut impl Rng) -> Vec<Color> {
         let mut groups = vec![Color::Purple];
         let mut possible_groups = vec![Color::Blue, Color::Green];
         match generator_settings.stop_sources {
@@ -119,7 +122,7 @@ fn allocate_groups(
             super::SourceSettings::None => (),
             super::SourceSettings::Maybe => possible_groups.extend([Color::CCW, Color::CW]),
             super::SourceSettings::Definitely => {
-                if chance(0.5) {
+                if chance(rng, 0.5) {
                     groups.push(Color::CCW);
                     possible_groups.push(Color::CW);
                 } else {
@@ -131,17 +134,17 @@ fn allocate_groups(
         while !possible_groups.is_empty() {
             let mut add = groups.len() < generator_settings.min_regions + 1;
             if !add {
-                add = chance(generator_settings.extra_region_chance)
+                add = chance(rng, generator_settings.extra_region_chance)
             }
             if !add {
                 break;
             }
 
-            groups.push(possible_groups.swap_remove(random_index(possible_groups.len())));
+            groups.push(possible_groups.swap_remove(random_index(rng, possible_groups.len())));
         }
         groups
     }
-    let mut groups: Vec<_> = get_group_colors(generator_settings)
+    let mut groups: Vec<_> = get_group_colors(generator_settings, rng)
         .into_iter()
         .map(Group::new)
         .collect();
@@ -149,7 +152,7 @@ fn allocate_groups(
     let mut intersections_left = generator_settings.max_intersections;
 
     while group_grid.len() < grid.len() {
-        groups.sort_by_cached_key(|_| rand::random::<u32>());
+        groups.sort_by_cached_key(|_| rng.gen::<u32>());
         groups.sort_by_key(|g| g.status());
         if let Some(group) = groups.last() {
             let add = if group.present.is_empty() {
@@ -157,15 +160,15 @@ fn allocate_groups(
                     .iter()
                     .map(|(p, _)| p)
                     .filter(|p| !group_grid.contains(*p))
-                    .choose(&mut rand::thread_rng())
+                    .choose(rng)
                     .unwrap();
                 Ok(position)
-            } else if let Some(&position) = group.boundary.iter().choose(&mut rand::thread_rng()) {
+            } else if let Some(&position) = group.boundary.iter().choose(rng) {
                 let add = match group_grid.get(position) {
                     Some(GeneratorCell::SingleGroup(in_group)) => {
                         *in_group != group.color
                             && intersections_left > 0
-                            && chance(generator_settings.intersection_chance)
+                            && chance(rng, generator_settings.intersection_chance)
                     }
                     Some(GeneratorCell::Intersection(_, _)) => false,
                     None => true,
@@ -232,10 +235,10 @@ fn verify(grid: Grid<PuzzleCell>) -> Result<Grid<PuzzleCell>, GeneratorFailure>
     Ok(grid)
 }
 
-fn random_index(length: usize) -> usize {
-    rand::random::<usize>() % length
+fn random_index(rng: &mut impl Rng, length: usize) -> usize {
+    rng.gen_range(0..length as u32) as usize
 }
 
-fn chance(c: f32) -> bool {
-    rand::random::<f32>() < c
+fn chance(rng: &mut impl Rng, c: f32) -> bool {
+    rng.gen::<f32>() < c
 }
@@ -0,0 +1,37 @@
+use crate::{
+    gameplay::{Cell, SwapRecord},
+    grids::Grid,
+};
+
+use super::{
+    background_task::{BackgroundTask, Job},
+    solver::SolveJob,
+    SolverError, SolverLimits,
+};
+
+/// A solution search running off the UI thread. Dropping the task cancels it.
+pub struct SolveTask {
+    task: BackgroundTask<SolveJob>,
+}
+
+impl SolveTask {
+    /// Starts looking for a shortest solution from `grid` in at most `maximum_swaps` swaps.
+    pub fn start(grid: &Grid<Cell>, maximum_swaps: u8, limits: SolverLimits) -> Self {
+        Self {
+            task: BackgroundTask::start(SolveJob::new(grid, maximum_swaps, limits)),
+        }
+    }
+
+    /// Returns the result once the search has finished.
+    pub fn poll(&mut self) -> Option<Result<Option<Vec<SwapRecord>>, SolverError>> {
+        self.task.poll()
+    }
+}
+
+impl Job for SolveJob {
+    type Output = Result<Option<Vec<SwapRecord>>, SolverError>;
+
+    fn step(&mut self) -> Option<Self::Output> {
+        SolveJob::step(self)
+    }
+}
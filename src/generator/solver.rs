@@ -1,8 +1,96 @@
+use std::{collections::VecDeque, time::Duration};
+
+use egui::ahash::{HashSet, HashSetExt};
+use web_time::Instant;
+
 use crate::{
-    gameplay::{Cell, GameGrid, GridSolveState, Puzzle, SwapRecord},
+    gameplay::{Cell, Connectivity, GameGrid, GridSolveState, Puzzle, SwapRecord},
     grids::{Grid, GridIndex, Rotation},
 };
 
+/// Limits on how much of the search space the solver may explore.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverLimits {
+    pub max_nodes: Option<usize>,
+    pub max_duration: Option<Duration>,
+}
+
+impl Default for SolverLimits {
+    fn default() -> Self {
+        Self {
+            max_nodes: Some(1_000_000),
+            max_duration: None,
+        }
+    }
+}
+
+/// The solver gave up before it could prove whether a solution exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolverError {
+    NodeLimit,
+    TimeLimit,
+}
+
+impl std::fmt::Display for SolverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolverError::NodeLimit => f.write_str("solver node limit reached"),
+            SolverError::TimeLimit => f.write_str("solver time limit reached"),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+struct LimitTracker {
+    max_nodes: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+impl LimitTracker {
+    fn start(limits: SolverLimits) -> Self {
+        Self {
+            max_nodes: limits.max_nodes,
+            deadline: limits
+                .max_duration
+                .map(|duration| Instant::now() + duration),
+        }
+    }
+
+    fn check(&self, nodes: usize) -> Result<(), SolverError> {
+        if self.max_nodes.is_some_and(|max| nodes >= max) {
+            return Err(SolverError::NodeLimit);
+        }
+        // Reading the clock is comparatively slow, so only check it occasionally.
+        if nodes % 256 == 0
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(SolverError::TimeLimit);
+        }
+        Ok(())
+    }
+}
+
+/// Compact encoding of the cells in a grid.
+///
+/// The fill of a grid is determined by its cells, so two grids with the same key
+/// are the same search state.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GridKey(Box<[u16]>);
+
+impl GridKey {
+    fn new(grid: &Grid<Cell>) -> Self {
+        Self(
+            grid.size()
+                .into_iter()
+                .map(|index| grid.get(index).map(Cell::layout_key).unwrap_or(0))
+                .collect(),
+        )
+    }
+}
+
 fn swap_without_fill(
     grid: &mut Grid<Cell>,
     a: GridIndex,
@@ -15,7 +103,7 @@ fn swap_without_fill(
     grid.swap(a, b);
 }
 
-fn get_possible_swaps(grid: &Grid<Cell>) -> Vec<SwapRecord> {
+pub(super) fn get_possible_swaps(grid: &Grid<Cell>) -> Vec<SwapRecord> {
     let entries: Vec<_> = grid.iter().collect();
     let mut swaps = Vec::new();
     for i in 0..(entries.len() - 1) {
@@ -34,44 +122,520 @@ fn get_possible_swaps(grid: &Grid<Cell>) -> Vec<SwapRecord> {
     swaps
 }
 
-pub fn find_solution(puzzle: &Puzzle, maximum_swaps: u8) -> Option<Vec<SwapRecord>> {
-    let mut grid = Grid::from_puzzle_grid(puzzle.start());
-    grid.fill();
-    if grid.is_solved() == GridSolveState::Solved {
-        Some(vec![])
-    } else if maximum_swaps == 0 {
-        None
-    } else if let Some(mut reverse_sol) = find_solution_from_grid(&mut grid, maximum_swaps) {
-        reverse_sol.reverse();
-        Some(reverse_sol)
-    } else {
-        None
+/// Finds a shortest solution using at most `maximum_swaps` swaps.
+///
+/// Returns `Ok(None)` if no such solution exists, or an error if the limits were
+/// reached before the search could finish.
+pub fn find_solution(
+    puzzle: &Puzzle,
+    maximum_swaps: u8,
+    limits: SolverLimits,
+) -> Result<Option<Vec<SwapRecord>>, SolverError> {
+    let grid = Grid::from_puzzle_grid(puzzle.start());
+    find_solution_from_grid(&grid, maximum_swaps, limits)
+}
+
+struct SearchNode {
+    parent: Option<usize>,
+    swap: SwapRecord,
+}
+
+fn find_solution_from_grid(
+    start: &Grid<Cell>,
+    maximum_swaps: u8,
+    limits: SolverLimits,
+) -> Result<Option<Vec<SwapRecord>>, SolverError> {
+    let mut job = SolveJob::new(start, maximum_swaps, limits);
+    loop {
+        if let Some(result) = job.step() {
+            return result;
+        }
     }
 }
 
-fn find_solution_from_grid(grid: &mut Grid<Cell>, swaps_left: u8) -> Option<Vec<SwapRecord>> {
-    let swaps = get_possible_swaps(grid);
-    for swap in swaps {
-        swap_without_fill(grid, swap.a, swap.a_rotation, swap.b, swap.b_rotation);
+/// The search behind [`find_solution`], run a little at a time so it can be spread over
+/// frames or moved to another thread.
+///
+/// Only the swaps leading to each position are kept, and a position's grid is rebuilt
+/// from the start when it is expanded, since a grid per queued position quickly runs
+/// into hundreds of megabytes.
+pub struct SolveJob {
+    tracker: LimitTracker,
+    maximum_swaps: u8,
+    start: Grid<Cell>,
+    start_solved: bool,
+    nodes: Vec<SearchNode>,
+    visited: HashSet<GridKey>,
+    frontier: VecDeque<Option<usize>>,
+}
+
+impl SolveJob {
+    /// Starts a search for a shortest solution from any grid, e.g. a puzzle partway
+    /// through being played.
+    pub fn new(start: &Grid<Cell>, maximum_swaps: u8, limits: SolverLimits) -> Self {
+        let mut grid = start.clone();
         grid.fill();
+        let start_solved = grid.is_solved() == GridSolveState::Solved;
 
-        if grid.is_solved() == GridSolveState::Solved {
-            return Some(vec![swap]);
+        let mut visited = HashSet::new();
+        visited.insert(GridKey::new(&grid));
+        let mut frontier = VecDeque::new();
+        if !start_solved {
+            frontier.push_back(None);
+        }
+        Self {
+            tracker: LimitTracker::start(limits),
+            maximum_swaps,
+            start: grid,
+            start_solved,
+            nodes: Vec::new(),
+            visited,
+            frontier,
         }
-        if swaps_left > 1 {
-            if let Some(mut solution) = find_solution_from_grid(grid, swaps_left - 1) {
-                solution.push(swap);
-                return Some(solution);
+    }
+
+    /// Tries every swap from one position, returning the result once the search is
+    /// finished.
+    pub fn step(&mut self) -> Option<Result<Option<Vec<SwapRecord>>, SolverError>> {
+        if self.start_solved {
+            return Some(Ok(Some(vec![])));
+        }
+        let Some(node) = self.frontier.pop_front() else {
+            return Some(Ok(None));
+        };
+        let (grid, depth) = self.grid_at(node);
+        if depth >= self.maximum_swaps as usize {
+            return None;
+        }
+        for swap in get_possible_swaps(&grid) {
+            if let Err(err) = self.tracker.check(self.nodes.len()) {
+                return Some(Err(err));
+            }
+
+            let mut next = grid.clone();
+            swap_without_fill(&mut next, swap.a, swap.a_rotation, swap.b, swap.b_rotation);
+            if !self.visited.insert(GridKey::new(&next)) {
+                continue;
+            }
+            self.nodes.push(SearchNode { parent: node, swap });
+            let next_node = self.nodes.len() - 1;
+            if Connectivity::new(&next).state() == GridSolveState::Solved {
+                return Some(Ok(Some(solution_to(&self.nodes, next_node))));
             }
+            self.frontier.push_back(Some(next_node));
+        }
+        None
+    }
+
+    /// The filled grid after the swaps leading to `node`, and how many swaps that is.
+    fn grid_at(&self, node: Option<usize>) -> (Grid<Cell>, usize) {
+        let mut grid = self.start.clone();
+        let Some(node) = node else {
+            return (grid, 0);
         };
+        // A swap only moves and rotates tiles, so the fill can wait until the end.
+        let swaps = solution_to(&self.nodes, node);
+        for swap in &swaps {
+            swap_without_fill(&mut grid, swap.a, swap.a_rotation, swap.b, swap.b_rotation);
+        }
+        grid.fill();
+        (grid, swaps.len())
+    }
+}
 
-        swap_without_fill(
+fn solution_to(nodes: &[SearchNode], node: usize) -> Vec<SwapRecord> {
+    let mut solution = Vec::new();
+    let mut node = Some(node);
+    while let Some(index) = node {
+        solution.push(nodes[index].swap);
+        node = nodes[index].parent;
+    }
+    solution.reverse();
+    solution
+}
+
+/// Counts the distinct solutions using at most `maximum_swaps` swaps, stopping at `cap`.
+///
+/// A solution is the sequence of swaps made, rotations included, so the same swaps in a
+/// different order are different solutions. Each pair of tiles is only tried one way
+/// round, so a swap between `a` and `b` is never counted again as one between `b` and
+/// `a`. Solutions are not extended past the first solved position, and never revisit a
+/// position.
+pub fn count_solutions(
+    puzzle: &Puzzle,
+    maximum_swaps: u8,
+    cap: usize,
+    limits: SolverLimits,
+) -> Result<usize, SolverError> {
+    let grid = Grid::from_puzzle_grid(puzzle.start());
+    let mut job = CountJob::new(&grid, maximum_swaps, cap, limits);
+    loop {
+        if let Some(result) = job.step() {
+            return result;
+        }
+    }
+}
+
+/// The search behind [`count_solutions`], run a little at a time.
+///
+/// A depth-first search, with the positions along the current path kept on a stack.
+pub struct CountJob {
+    tracker: LimitTracker,
+    nodes: usize,
+    cap: usize,
+    path_keys: HashSet<GridKey>,
+    path: Vec<CountFrame>,
+    // Every path the search takes is a different sequence of swaps, so each solved
+    // position it reaches is a new solution.
+    found: usize,
+}
+
+struct CountFrame {
+    grid: Grid<Cell>,
+    key: GridKey,
+    swaps: Vec<SwapRecord>,
+    next_swap: usize,
+    swaps_left: u8,
+}
+
+impl CountJob {
+    pub fn new(start: &Grid<Cell>, maximum_swaps: u8, cap: usize, limits: SolverLimits) -> Self {
+        let mut grid = start.clone();
+        grid.fill();
+        let mut job = Self {
+            tracker: LimitTracker::start(limits),
+            nodes: 0,
+            cap,
+            path_keys: HashSet::new(),
+            path: Vec::new(),
+            found: 0,
+        };
+        if grid.is_solved() == GridSolveState::Solved {
+            job.found = 1;
+        } else {
+            job.push(GridKey::new(&grid), grid, maximum_swaps);
+        }
+        job
+    }
+
+    /// Tries a single swap, returning the count once the search is finished.
+    pub fn step(&mut self) -> Option<Result<usize, SolverError>> {
+        if self.found >= self.cap {
+            return Some(Ok(self.cap));
+        }
+        let Some(frame) = self.path.last_mut() else {
+            return Some(Ok(self.found));
+        };
+        let Some(&swap) = frame.swaps.get(frame.next_swap) else {
+            let frame = self.path.pop().unwrap();
+            self.path_keys.remove(&frame.key);
+            return None;
+        };
+        frame.next_swap += 1;
+        if let Err(err) = self.tracker.check(self.nodes) {
+            return Some(Err(err));
+        }
+        self.nodes += 1;
+
+        let mut next = frame.grid.clone();
+        swap_without_fill(&mut next, swap.a, swap.a_rotation, swap.b, swap.b_rotation);
+        let key = GridKey::new(&next);
+        if self.path_keys.contains(&key) {
+            return None;
+        }
+        let connectivity = Connectivity::new(&next);
+        next.fill_from(&connectivity);
+
+        let swaps_left = frame.swaps_left;
+        if connectivity.state() == GridSolveState::Solved {
+            self.found += 1;
+        } else if swaps_left > 1 {
+            self.push(key, next, swaps_left - 1);
+        }
+        None
+    }
+
+    fn push(&mut self, key: GridKey, grid: Grid<Cell>, swaps_left: u8) {
+        self.path_keys.insert(key.clone());
+        self.path.push(CountFrame {
+            swaps: get_possible_swaps(&grid),
             grid,
-            swap.a,
-            swap.b_rotation.inverse(),
-            swap.b,
-            swap.a_rotation.inverse(),
+            key,
+            next_swap: 0,
+            swaps_left,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gameplay::debug_puzzle,
+        generator::{try_generate_puzzle_with_budget, GenerationBudget, GeneratorSettings},
+        grids::GridSize,
+    };
+
+    const UNLIMITED: SolverLimits = SolverLimits {
+        max_nodes: None,
+        max_duration: None,
+    };
+
+    fn filled(puzzle: &Puzzle) -> Grid<Cell> {
+        let mut grid = Grid::from_puzzle_grid(puzzle.start());
+        grid.fill();
+        grid
+    }
+
+    /// Small generated puzzles that need exactly `swaps` swaps.
+    fn puzzles(swaps: u8) -> Vec<Puzzle> {
+        (0..4)
+            .map(|seed| {
+                let settings = GeneratorSettings {
+                    size: GridSize::new(3, 3),
+                    swap_count: swaps,
+                    check_solution_len: swaps as usize - 1,
+                    seed: Some(seed),
+                    ..Default::default()
+                };
+                try_generate_puzzle_with_budget(&settings, GenerationBudget::default()).unwrap()
+            })
+            .collect()
+    }
+
+    /// Every position reachable from `grid` in at most `swaps` swaps, made the way the game
+    /// makes them.
+    fn reachable(grid: &Grid<Cell>, swaps: usize, found: &mut Vec<Grid<Cell>>) {
+        if swaps == 0 {
+            return;
+        }
+        let indices: Vec<_> = grid.indicies().collect();
+        for (i, &a) in indices.iter().enumerate() {
+            for &b in &indices[i + 1..] {
+                let mut next = grid.clone();
+                if next.swap_with_rotation(a, b).is_some() {
+                    next.fill();
+                    reachable(&next, swaps - 1, found);
+                    found.push(next);
+                }
+            }
+        }
+    }
+
+    fn brute_force_shortest(puzzle: &Puzzle) -> Option<usize> {
+        let grid = filled(puzzle);
+        (0..=puzzle.swap_limit() as usize).find(|&swaps| {
+            let mut found = vec![grid.clone()];
+            reachable(&grid, swaps, &mut found);
+            found
+                .iter()
+                .any(|grid| grid.is_solved() == GridSolveState::Solved)
+        })
+    }
+
+    fn assert_solves(puzzle: &Puzzle, solution: &[SwapRecord]) {
+        let mut grid = filled(puzzle);
+        for swap in solution {
+            assert_eq!(grid.swap_with_rotation(swap.a, swap.b), Some(*swap));
+            grid.fill();
+        }
+        assert_eq!(grid.is_solved(), GridSolveState::Solved);
+    }
+
+    #[test]
+    fn solutions_are_shortest() {
+        let mut all = vec![debug_puzzle::test_puzzle()];
+        all.extend(puzzles(2));
+        all.extend(puzzles(3));
+        for puzzle in all {
+            let solution = find_solution(&puzzle, puzzle.swap_limit(), UNLIMITED)
+                .unwrap()
+                .unwrap();
+            assert_eq!(Some(solution.len()), brute_force_shortest(&puzzle));
+        }
+    }
+
+    #[test]
+    fn solutions_solve_the_grid() {
+        for puzzle in puzzles(3) {
+            let solution = find_solution(&puzzle, puzzle.swap_limit(), UNLIMITED)
+                .unwrap()
+                .unwrap();
+            assert_solves(&puzzle, &solution);
+        }
+    }
+
+    #[test]
+    fn too_few_swaps_has_no_solution() {
+        for puzzle in puzzles(3) {
+            assert_eq!(find_solution(&puzzle, 2, UNLIMITED), Ok(None));
+        }
+    }
+
+    #[test]
+    fn limits_give_up_instead_of_finding_no_solution() {
+        for puzzle in puzzles(3) {
+            let nodes = SolverLimits {
+                max_nodes: Some(1),
+                max_duration: None,
+            };
+            assert_eq!(
+                find_solution(&puzzle, puzzle.swap_limit(), nodes),
+                Err(SolverError::NodeLimit)
+            );
+            assert_eq!(
+                find_solution(&puzzle, 2, nodes),
+                Err(SolverError::NodeLimit)
+            );
+
+            let time = SolverLimits {
+                max_nodes: None,
+                max_duration: Some(Duration::ZERO),
+            };
+            assert_eq!(
+                find_solution(&puzzle, puzzle.swap_limit(), time),
+                Err(SolverError::TimeLimit)
+            );
+        }
+    }
+
+    #[test]
+    fn positions_are_searched_once() {
+        for puzzle in puzzles(3) {
+            // Without a solution in reach the whole space is searched.
+            let start = filled(&puzzle);
+            let mut job = SolveJob::new(&start, 2, UNLIMITED);
+            let result = loop {
+                if let Some(result) = job.step() {
+                    break result;
+                }
+            };
+            assert_eq!(result, Ok(None));
+
+            let mut keys = HashSet::new();
+            keys.insert(GridKey::new(&start));
+            for node in 0..job.nodes.len() {
+                assert!(keys.insert(GridKey::new(&job.grid_at(Some(node)).0)));
+            }
+
+            let mut found = Vec::new();
+            reachable(&start, 2, &mut found);
+            let distinct: HashSet<_> = found.iter().map(GridKey::new).collect();
+            assert!(
+                found.len() > distinct.len(),
+                "some positions are transposed"
+            );
+            assert_eq!(
+                keys,
+                distinct.into_iter().chain([GridKey::new(&start)]).collect()
+            );
+        }
+    }
+
+    /// Every sequence of swaps that solves `grid`, following the rules of
+    /// [`count_solutions`] with the game's own swaps.
+    fn solving_sequences(
+        grid: &Grid<Cell>,
+        swaps_left: u8,
+        path: &mut Vec<SwapRecord>,
+        seen: &mut Vec<GridKey>,
+        found: &mut Vec<Vec<SwapRecord>>,
+    ) {
+        let indices: Vec<_> = grid.indicies().collect();
+        for (i, &a) in indices.iter().enumerate() {
+            for &b in &indices[i + 1..] {
+                let mut next = grid.clone();
+                let Some(record) = next.swap_with_rotation(a, b) else {
+                    continue;
+                };
+                next.fill();
+                let key = GridKey::new(&next);
+                if seen.contains(&key) {
+                    continue;
+                }
+                path.push(record);
+                if next.is_solved() == GridSolveState::Solved {
+                    found.push(path.clone());
+                } else if swaps_left > 1 {
+                    seen.push(key);
+                    solving_sequences(&next, swaps_left - 1, path, seen, found);
+                    seen.pop();
+                }
+                path.pop();
+            }
+        }
+    }
+
+    #[test]
+    fn solutions_are_counted_once_per_pair() {
+        let puzzle: Puzzle = "swaps: 2\nhint: 0 0\ngrid:\nW E@p\n".parse().unwrap();
+        let swaps = get_possible_swaps(&filled(&puzzle));
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(count_solutions(&puzzle, 1, 10, UNLIMITED), Ok(1));
+        for puzzle in puzzles(3) {
+            let swaps = get_possible_swaps(&filled(&puzzle));
+            for swap in &swaps {
+                assert!(!swaps
+                    .iter()
+                    .any(|other| (other.a, other.b) == (swap.b, swap.a)));
+            }
+        }
+        // Swapping back would revisit the start.
+        assert_eq!(count_solutions(&puzzle, 2, 10, UNLIMITED), Ok(1));
+    }
+
+    #[test]
+    fn solution_counts_stop_at_the_cap() {
+        let puzzle = &puzzles(3)[2];
+        let count = count_solutions(puzzle, 4, usize::MAX, UNLIMITED).unwrap();
+        assert!(count > 2);
+        assert_eq!(count_solutions(puzzle, 4, 2, UNLIMITED), Ok(2));
+        assert_eq!(count_solutions(puzzle, 4, 0, UNLIMITED), Ok(0));
+        let solved: Puzzle = "swaps: 1\nhint: 0 0\ngrid:\nE@p W\n".parse().unwrap();
+        assert_eq!(count_solutions(&solved, 1, 10, UNLIMITED), Ok(1));
+        assert_eq!(count_solutions(&solved, 1, 0, UNLIMITED), Ok(0));
+    }
+
+    #[test]
+    fn every_order_of_the_swaps_is_counted() {
+        let mut reordered = false;
+        for puzzle in puzzles(3) {
+            let start = filled(&puzzle);
+            let mut found = Vec::new();
+            solving_sequences(
+                &start,
+                4,
+                &mut Vec::new(),
+                &mut vec![GridKey::new(&start)],
+                &mut found,
+            );
+            assert_eq!(
+                count_solutions(&puzzle, 4, usize::MAX, UNLIMITED),
+                Ok(found.len())
+            );
+            let unordered: HashSet<Vec<(GridIndex, GridIndex)>> = found
+                .iter()
+                .map(|solution| {
+                    let mut pairs: Vec<_> = solution.iter().map(|swap| (swap.a, swap.b)).collect();
+                    pairs.sort();
+                    pairs
+                })
+                .collect();
+            reordered |= unordered.len() < found.len();
+        }
+        assert!(reordered);
+    }
+
+    #[test]
+    fn solution_counts_give_up_at_the_limits() {
+        let puzzle = &puzzles(3)[0];
+        let limits = SolverLimits {
+            max_nodes: Some(1),
+            max_duration: None,
+        };
+        assert_eq!(
+            count_solutions(puzzle, 3, 10, limits),
+            Err(SolverError::NodeLimit)
         );
     }
-    None
 }
@@ -118,18 +118,20 @@ impl<T> IndexMut<&Direction> for DirectionMap<T> {
     }
 }
 
-impl DirectionSet {
-    pub fn empty() -> Self {
-        Self::new_with_repeat(false)
-    }
-
-    pub fn from_iter(iter: impl Iterator<Item = Direction>) -> Self {
+impl FromIterator<Direction> for DirectionSet {
+    fn from_iter<I: IntoIterator<Item = Direction>>(iter: I) -> Self {
         let mut set = Self::empty();
         for dir in iter {
             set.insert(dir);
         }
         set
     }
+}
+
+impl DirectionSet {
+    pub fn empty() -> Self {
+        Self::new_with_repeat(false)
+    }
 
     pub fn contains(&self, direction: Direction) -> bool {
         self[direction]
@@ -158,4 +160,17 @@ impl DirectionSet {
     pub fn iter_set(&self) -> impl Iterator<Item = Direction> + '_ {
         Direction::ALL.into_iter().filter(|d| self[d])
     }
+
+    /// The set as a bitmask, with one bit per direction.
+    pub fn bits(&self) -> u8 {
+        self.iter_set().fold(0, |bits, dir| bits | 1 << idx(dir))
+    }
+
+    /// The inverse of [`DirectionSet::bits`]; bits above the fourth are ignored.
+    pub fn from_bits(bits: u8) -> Self {
+        Direction::ALL
+            .into_iter()
+            .filter(|dir| bits & 1 << idx(*dir) != 0)
+            .collect()
+    }
 }
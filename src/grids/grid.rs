@@ -54,6 +54,13 @@ impl<T> Grid<T> {
         self.size
     }
 
+    /// Whether the storage agrees with the size and cell count, which a deserialized grid
+    /// does not guarantee.
+    pub fn is_consistent(&self) -> bool {
+        self.data.len() == self.size.width * self.size.height
+            && self.data.iter().filter(|cell| cell.is_some()).count() == self.filled
+    }
+
     pub const fn width(&self) -> usize {
         self.size.width
     }
@@ -66,6 +73,10 @@ impl<T> Grid<T> {
         self.filled
     }
 
+    pub const fn is_empty(&self) -> bool {
+        self.filled == 0
+    }
+
     pub fn insert(&mut self, grid_index: GridIndex, value: T) -> Result<Option<T>, IndexOutOfSize> {
         let li = get_linear_index(self.size, grid_index).ok_or(IndexOutOfSize)?;
         let previous = self.data[li].take();
@@ -93,6 +104,9 @@ impl<T> Grid<T> {
         get_linear_index(self.size, grid_index).and_then(|li| self.data[li].as_ref())
     }
 
+    /// # Safety
+    ///
+    /// `grid_index` must be within the grid's size.
     pub unsafe fn get_unchecked(&self, grid_index: GridIndex) -> Option<&T> {
         self.data
             .get_unchecked(get_linear_index_unchecked(self.size, grid_index))
@@ -103,6 +117,9 @@ impl<T> Grid<T> {
         get_linear_index(self.size, grid_index).and_then(|li| self.data[li].as_mut())
     }
 
+    /// # Safety
+    ///
+    /// `grid_index` must be within the grid's size.
     pub unsafe fn get_unchecked_mut(&mut self, grid_index: GridIndex) -> Option<&mut T> {
         self.data
             .get_unchecked_mut(get_linear_index_unchecked(self.size, grid_index))
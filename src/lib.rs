@@ -1,8 +1,17 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+#[cfg(feature = "gui")]
 mod app;
-mod gameplay;
-mod generator;
-mod grids;
+#[cfg(feature = "gui")]
+mod deep_link;
+pub mod gameplay;
+pub mod generator;
+pub mod grids;
+#[cfg(feature = "gui")]
+mod puzzle_queue;
+#[cfg(feature = "gui")]
+mod save;
+#[cfg(feature = "gui")]
 mod ux;
+#[cfg(feature = "gui")]
 pub use app::App;
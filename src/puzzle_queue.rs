@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+use crate::{
+    gameplay::{Puzzle, PuzzleError},
+    generator::{GenerationBudget, GenerationTask},
+    ux::SettingsConfig,
+};
+
+/// Upcoming puzzles, generated in the background so the next puzzle is ready immediately.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+pub struct PuzzleQueue {
+    // The settings the queued puzzles were generated with.
+    config: Option<SettingsConfig>,
+    puzzles: VecDeque<Puzzle>,
+    #[serde(skip)]
+    task: Option<GenerationTask>,
+    #[serde(skip)]
+    failed: bool,
+}
+
+impl PuzzleQueue {
+    const CAPACITY: usize = 3;
+
+    /// Drops the queue if the generator settings changed, and keeps generating until it
+    /// is full.
+    pub fn update(&mut self, ctx: &egui::Context, config: &SettingsConfig) {
+        if !self.generated_with(config) {
+            self.config = Some(config.clone());
+            self.puzzles.clear();
+            self.task = None;
+            self.failed = false;
+        }
+
+        if let Some(task) = &mut self.task {
+            if let Some(result) = task.poll() {
+                self.task = None;
+                match result {
+                    Ok(puzzle) => self.puzzles.push_back(puzzle),
+                    Err(err) => {
+                        log::warn!("Could not fill puzzle queue: {err}");
+                        self.failed = true;
+                    }
+                }
+            }
+        }
+
+        if self.task.is_none() && !self.failed && self.puzzles.len() < Self::CAPACITY {
+            self.task = Some(GenerationTask::start(
+                config.get_current_settings(),
+                GenerationBudget::default(),
+            ));
+        }
+        // The result is only picked up, and on the web only generated, while repainting.
+        if self.task.is_some() {
+            ctx.request_repaint();
+        }
+    }
+
+    /// The attempts made on the puzzle being generated for the given settings, if any.
+    pub fn filling(&self, config: &SettingsConfig) -> Option<usize> {
+        self.task
+            .as_ref()
+            .filter(|_| self.generated_with(config))
+            .map(GenerationTask::attempts)
+    }
+
+    /// Drops saved puzzles that fail validation, e.g. from an older or edited save.
+    pub fn discard_invalid(&mut self) {
+        self.puzzles.retain(|puzzle| match puzzle.validate() {
+            Ok(()) => true,
+            Err(errors) => {
+                log::warn!(
+                    "Discarding queued puzzle: {}",
+                    PuzzleError::describe_all(&errors)
+                );
+                false
+            }
+        });
+    }
+
+    /// Takes the next puzzle, if one was generated with the given settings.
+    pub fn pop(&mut self, config: &SettingsConfig) -> Option<Puzzle> {
+        if self.generated_with(config) {
+            self.puzzles.pop_front()
+        } else {
+            None
+        }
+    }
+
+    fn generated_with(&self, config: &SettingsConfig) -> bool {
+        self.config
+            .as_ref()
+            .is_some_and(|queued| queued.generates_like(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::{fallback_puzzle, Difficulty};
+
+    fn queue_for(config: &SettingsConfig) -> PuzzleQueue {
+        PuzzleQueue {
+            config: Some(config.clone()),
+            puzzles: VecDeque::from([fallback_puzzle(), fallback_puzzle()]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn play_options_keep_the_queue() {
+        let config = SettingsConfig::default();
+        let mut queue = queue_for(&config);
+        let toggled = SettingsConfig {
+            check_button: !config.check_button,
+            warn_when_stuck: !config.warn_when_stuck,
+            ..config.clone()
+        };
+        assert!(queue.pop(&toggled).is_some());
+        assert!(queue.pop(&config).is_some());
+    }
+
+    #[test]
+    fn generator_settings_drop_the_queue() {
+        let config = SettingsConfig::default();
+        let mut queue = queue_for(&config);
+        let harder = SettingsConfig {
+            target_difficulty: Some(Difficulty::Hard),
+            ..config.clone()
+        };
+        assert!(queue.pop(&harder).is_none());
+        let custom = SettingsConfig {
+            custom_override: true,
+            ..config
+        };
+        assert!(queue.pop(&custom).is_none());
+    }
+}
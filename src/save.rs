@@ -0,0 +1,335 @@
+//! Versioned save data.
+//!
+//! Everything the game persists is written as one JSON envelope holding a format version,
+//! so older saves can be upgraded explicitly instead of failing to deserialize. A save
+//! that cannot be loaded is kept under a backup key rather than being overwritten.
+
+use serde_json::Value;
+
+use crate::{
+    gameplay::{PlayingPuzzle, Puzzle, SwapRecord},
+    puzzle_queue::PuzzleQueue,
+    ux::{PuzzleState, SettingsConfig},
+};
+
+/// The version written by this build. Version 0 is the unversioned format, which kept each
+/// value under its own key.
+pub const SAVE_VERSION: u32 = 2;
+
+const SAVE_KEY: &str = "swap_save";
+const BACKUP_KEY: &str = "swap_save_backup";
+
+const LEGACY_PUZZLE_KEY: &str = "swap_puzzle";
+const LEGACY_PUZZLE_QUEUE_KEY: &str = "swap_puzzle_queue";
+const LEGACY_PUZZLE_STATE_KEY: &str = "swap_puzzle_state";
+const LEGACY_SETTINGS_KEY: &str = "swap_settings";
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// Upgrades for versioned saves: entry `n` turns version `n + 1` data into version `n + 2`.
+/// Add an entry here whenever `SAVE_VERSION` is bumped.
+const MIGRATIONS: &[Migration] = &[branch_history];
+
+/// The puzzle as saved before moves were kept as a tree: a single line of swaps, made up
+/// of `history` and then the swaps undone from it.
+#[derive(serde::Deserialize)]
+struct LinearPlayingPuzzle {
+    puzzle: Puzzle,
+    history: Vec<SwapRecord>,
+    #[serde(default)]
+    undone: Vec<SwapRecord>,
+}
+
+impl LinearPlayingPuzzle {
+    fn resume(self) -> PlayingPuzzle {
+        let applied = self.history.len();
+        let mut line = self.history;
+        line.extend(self.undone.into_iter().rev());
+        PlayingPuzzle::resume(self.puzzle, line, applied)
+    }
+}
+
+/// Version 1 to 2: the linear move history became a tree.
+fn branch_history(mut data: Value) -> Result<Value, String> {
+    if let Some(puzzle) = data.get_mut("puzzle").filter(|puzzle| !puzzle.is_null()) {
+        let linear: LinearPlayingPuzzle =
+            serde_json::from_value(puzzle.take()).map_err(|err| err.to_string())?;
+        *puzzle = serde_json::to_value(linear.resume()).map_err(|err| err.to_string())?;
+    }
+    Ok(data)
+}
+
+#[derive(serde::Deserialize)]
+struct Envelope {
+    version: u32,
+    data: Value,
+}
+
+#[derive(serde::Serialize)]
+struct EnvelopeRef<'a> {
+    version: u32,
+    data: SaveDataRef<'a>,
+}
+
+#[derive(Default, serde::Deserialize)]
+pub struct SaveData {
+    pub puzzle: Option<PlayingPuzzle>,
+    #[serde(default)]
+    pub puzzle_state: PuzzleState,
+    #[serde(default)]
+    pub config: SettingsConfig,
+    #[serde(default)]
+    pub queue: PuzzleQueue,
+}
+
+#[derive(serde::Serialize)]
+pub struct SaveDataRef<'a> {
+    pub puzzle: &'a PlayingPuzzle,
+    pub puzzle_state: &'a PuzzleState,
+    pub config: &'a SettingsConfig,
+    pub queue: &'a PuzzleQueue,
+}
+
+pub struct LoadedSave {
+    pub data: SaveData,
+    /// The raw save, if it could not be loaded and should be backed up on the next write.
+    pub backup: Option<String>,
+}
+
+fn migrate(envelope: Envelope) -> Result<SaveData, String> {
+    let Envelope { version, mut data } = envelope;
+    if version == 0 || version > SAVE_VERSION {
+        return Err(format!("unsupported save version {version}"));
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        data = migration(data)?;
+    }
+    serde_json::from_value(data).map_err(|err| err.to_string())
+}
+
+fn load_versioned(text: &str) -> Result<SaveData, String> {
+    let envelope: Envelope = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    migrate(envelope)
+}
+
+fn read_legacy<T: serde::de::DeserializeOwned>(
+    storage: &dyn eframe::Storage,
+    key: &str,
+    failed: &mut serde_json::Map<String, Value>,
+) -> Option<T> {
+    let value = eframe::get_value(storage, key);
+    if value.is_none() {
+        if let Some(raw) = storage.get_string(key) {
+            failed.insert(key.to_owned(), Value::String(raw));
+        }
+    }
+    value
+}
+
+/// Reads a version 0 save. Values that fail to load are collected into a backup.
+fn load_legacy(storage: &dyn eframe::Storage) -> LoadedSave {
+    let mut failed = serde_json::Map::new();
+    let data = SaveData {
+        puzzle: read_legacy(storage, LEGACY_PUZZLE_KEY, &mut failed)
+            .map(LinearPlayingPuzzle::resume),
+        puzzle_state: read_legacy(storage, LEGACY_PUZZLE_STATE_KEY, &mut failed)
+            .unwrap_or_default(),
+        config: read_legacy(storage, LEGACY_SETTINGS_KEY, &mut failed).unwrap_or_default(),
+        queue: read_legacy(storage, LEGACY_PUZZLE_QUEUE_KEY, &mut failed).unwrap_or_default(),
+    };
+    let backup = (!failed.is_empty()).then(|| {
+        log::warn!("Could not migrate parts of an unversioned save; keeping a backup");
+        Value::Object(failed).to_string()
+    });
+    LoadedSave { data, backup }
+}
+
+pub fn load(storage: Option<&dyn eframe::Storage>) -> LoadedSave {
+    let Some(storage) = storage else {
+        return LoadedSave {
+            data: SaveData::default(),
+            backup: None,
+        };
+    };
+    match storage.get_string(SAVE_KEY) {
+        Some(text) => match load_versioned(&text) {
+            Ok(data) => LoadedSave { data, backup: None },
+            Err(err) => {
+                log::warn!("Could not load save: {err}; keeping a backup");
+                LoadedSave {
+                    data: SaveData::default(),
+                    backup: Some(text),
+                }
+            }
+        },
+        None => load_legacy(storage),
+    }
+}
+
+pub fn write(storage: &mut dyn eframe::Storage, data: SaveDataRef<'_>, backup: Option<&str>) {
+    if let Some(backup) = backup {
+        storage.set_string(BACKUP_KEY, backup.to_owned());
+    }
+    let envelope = EnvelopeRef {
+        version: SAVE_VERSION,
+        data,
+    };
+    match serde_json::to_string(&envelope) {
+        Ok(text) => storage.set_string(SAVE_KEY, text),
+        Err(err) => log::error!("Could not write save: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use eframe::Storage;
+
+    use super::*;
+    use crate::grids::GridIndex;
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_owned(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn puzzle() -> Puzzle {
+        "swaps: 2\nhint: 0 0\ngrid:\nE@p W E@p W\n".parse().unwrap()
+    }
+
+    fn envelope(version: u32, data: Value) -> String {
+        serde_json::json!({ "version": version, "data": data }).to_string()
+    }
+
+    #[test]
+    fn current_saves_round_trip() {
+        let mut playing = PlayingPuzzle::play(puzzle());
+        playing
+            .try_swap(GridIndex::new(1, 0), GridIndex::new(2, 0))
+            .unwrap();
+        let puzzle_state = PuzzleState { solved: true };
+        let mut storage = MemoryStorage::default();
+        write(
+            &mut storage,
+            SaveDataRef {
+                puzzle: &playing,
+                puzzle_state: &puzzle_state,
+                config: &SettingsConfig::default(),
+                queue: &PuzzleQueue::default(),
+            },
+            None,
+        );
+
+        let loaded = load(Some(&storage));
+        assert!(loaded.backup.is_none());
+        let loaded_puzzle = loaded.data.puzzle.unwrap();
+        assert_eq!(loaded_puzzle.puzzle(), playing.puzzle());
+        assert_eq!(loaded_puzzle.moves(), playing.moves());
+        assert!(loaded.data.puzzle_state.solved);
+        assert_eq!(loaded.data.config, SettingsConfig::default());
+    }
+
+    #[test]
+    fn missing_fields_load_as_defaults() {
+        let data = load_versioned(&envelope(SAVE_VERSION, serde_json::json!({}))).unwrap();
+        assert!(data.puzzle.is_none());
+        assert!(!data.puzzle_state.solved);
+    }
+
+    #[test]
+    fn unreadable_saves_are_kept_as_a_backup() {
+        for text in [
+            envelope(0, serde_json::json!({})),
+            envelope(SAVE_VERSION + 1, serde_json::json!({})),
+            envelope(SAVE_VERSION, serde_json::json!({ "puzzle": 1 })),
+            "not json".to_owned(),
+        ] {
+            let mut storage = MemoryStorage::default();
+            storage.set_string(SAVE_KEY, text.clone());
+            let loaded = load(Some(&storage));
+            assert!(loaded.data.puzzle.is_none(), "{text}");
+            assert_eq!(loaded.backup, Some(text));
+        }
+    }
+
+    #[test]
+    fn writing_stores_the_backup() {
+        let playing = PlayingPuzzle::play(puzzle());
+        let mut storage = MemoryStorage::default();
+        write(
+            &mut storage,
+            SaveDataRef {
+                puzzle: &playing,
+                puzzle_state: &PuzzleState::default(),
+                config: &SettingsConfig::default(),
+                queue: &PuzzleQueue::default(),
+            },
+            Some("old save"),
+        );
+        assert_eq!(storage.get_string(BACKUP_KEY).as_deref(), Some("old save"));
+        assert!(load_versioned(&storage.get_string(SAVE_KEY).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn unversioned_saves_are_read_from_their_own_keys() {
+        let mut storage = MemoryStorage::default();
+        eframe::set_value(
+            &mut storage,
+            LEGACY_PUZZLE_STATE_KEY,
+            &PuzzleState { solved: true },
+        );
+        storage.set_string(LEGACY_PUZZLE_QUEUE_KEY, "not ron".to_owned());
+
+        let loaded = load(Some(&storage));
+        assert!(loaded.data.puzzle.is_none());
+        assert!(loaded.data.puzzle_state.solved);
+        let backup: Value = serde_json::from_str(&loaded.backup.unwrap()).unwrap();
+        assert_eq!(
+            backup,
+            serde_json::json!({ LEGACY_PUZZLE_QUEUE_KEY: "not ron" })
+        );
+    }
+
+    #[test]
+    fn linear_histories_become_trees() {
+        let mut playing = PlayingPuzzle::play(puzzle());
+        let made = playing
+            .try_swap(GridIndex::new(1, 0), GridIndex::new(2, 0))
+            .unwrap();
+        let undone = playing
+            .try_swap(GridIndex::new(1, 0), GridIndex::new(2, 0))
+            .unwrap();
+        let linear = serde_json::json!({
+            "puzzle": puzzle(),
+            "history": [made],
+            "undone": [undone],
+        });
+
+        let data = load_versioned(&envelope(1, serde_json::json!({ "puzzle": linear }))).unwrap();
+        let mut migrated = data.puzzle.unwrap();
+        assert_eq!(migrated.puzzle(), &puzzle());
+        assert_eq!(migrated.swaps_made(), 1);
+        assert_eq!(migrated.moves().line(), vec![made, undone]);
+        assert_eq!(migrated.try_redo(), Some(undone));
+    }
+
+    #[test]
+    fn version_one_saves_without_a_puzzle_migrate() {
+        let data = load_versioned(&envelope(1, serde_json::json!({ "puzzle": null }))).unwrap();
+        assert!(data.puzzle.is_none());
+        let data = load_versioned(&envelope(1, serde_json::json!({}))).unwrap();
+        assert!(data.puzzle.is_none());
+        assert!(load_versioned(&envelope(1, serde_json::json!({ "puzzle": {} }))).is_err());
+    }
+}
@@ -19,6 +19,8 @@ struct BackgroundData {
     swap_glow: f32,
     stop_glow: f32,
     rotation_glow: f32,
+    check_glow: f32,
+    check_marker: Option<CheckMarker>,
     last_rotation_color: Option<Color>,
     last_direction: Option<f32>,
     rotation_t: f32,
@@ -32,11 +34,25 @@ pub struct BackgroundAnimationDrawData {
     pub show_hint: bool,
 }
 
+/// A problem found on a tile when the player checks the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckMarker {
+    /// A path on the tile ends without meeting another.
+    OpenEnd,
+    /// A path on the tile is not joined to a source.
+    Unfilled,
+    /// The tile is reached by sources of different colors.
+    Conflict,
+    /// The tile is joined to some sources of this color but not to the others.
+    Split(Color),
+}
+
 impl BackgroundAnimation {
     const SWAP_GLOW_LOSS: f32 = 2.0;
     const STOP_GLOW_LOSS: f32 = 2.0;
     const ROTATION_GLOW_LOSS: f32 = 2.0;
     const ROTATION_SPEED: f32 = 0.3;
+    const CHECK_GLOW_LOSS: f32 = 0.4;
 
     pub fn new(grid: &Grid<Cell>) -> Self {
         let data = grid
@@ -46,6 +62,16 @@ impl BackgroundAnimation {
         Self { data }
     }
 
+    /// Marks tiles with problems. The markers fade out over a few seconds.
+    pub fn show_check(&mut self, markers: impl IntoIterator<Item = (GridIndex, CheckMarker)>) {
+        for (index, marker) in markers {
+            if let Some(data) = self.data.get_mut(&index) {
+                data.check_glow = 1.0;
+                data.check_marker = Some(marker);
+            }
+        }
+    }
+
     fn update_cell(data: &mut BackgroundData, cell: &Cell, dt: f32) {
         let stop = cell.has_color_in_any_layer(Color::STOP);
         data.swap_glow = if cell.has_color_in_any_layer(Color::SWAP) && !stop {
@@ -68,6 +94,7 @@ impl BackgroundAnimation {
         } else {
             (data.rotation_glow - dt * Self::ROTATION_GLOW_LOSS).max(0.0)
         };
+        data.check_glow = (data.check_glow - dt * Self::CHECK_GLOW_LOSS).max(0.0);
         data.last_rotation_color = match rotation {
             Rotation::CounterClockwise => Some(Color::CCW),
             Rotation::Clockwise => Some(Color::CW),
@@ -157,6 +184,26 @@ impl BackgroundAnimation {
             }
         }
 
+        if let Some(marker) = data.check_marker.filter(|_| data.check_glow > 0.001) {
+            let color = match marker {
+                CheckMarker::OpenEnd | CheckMarker::Unfilled => palette.empty,
+                CheckMarker::Conflict => palette.get(Color::STOP),
+                CheckMarker::Split(color) => palette.get(color),
+            };
+            let rect = Rect::from_center_size(center, Vec2::splat(scale * 0.85));
+            let stroke = Stroke::new(scale * 0.04, color.gamma_multiply(data.check_glow));
+            match marker {
+                CheckMarker::OpenEnd | CheckMarker::Unfilled => {
+                    painter.rect_stroke(rect, Rounding::same(scale * 0.05), stroke);
+                }
+                // Joined tiles are circled, so they stand apart from the tiles they
+                // should be joined to.
+                CheckMarker::Conflict | CheckMarker::Split(_) => {
+                    painter.circle_stroke(center, scale * 0.42, stroke);
+                }
+            }
+        }
+
         fn update_hint(
             hint_glow: &mut f32,
             painter: &Painter,
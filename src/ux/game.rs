@@ -4,12 +4,16 @@ use egui::{
 };
 
 use crate::{
-    gameplay::{Color, PlayingPuzzle, PuzzleSolveState, SwapRecord},
-    grids::GridIndex,
+    gameplay::{
+        square_name, Color, PlayingPuzzle, PuzzleSolveState, Replay, ReplayAction, SolveReport,
+        SwapRecord,
+    },
+    generator::{Hint, HintTask},
+    grids::{GridIndex, GridSize},
 };
 
 use super::{
-    background::{BackgroundAnimation, BackgroundAnimationDrawData},
+    background::{BackgroundAnimation, BackgroundAnimationDrawData, CheckMarker},
     cell::{draw_cell, CellDrawData},
     palette::{self, Palette},
     simulation::Simulation,
@@ -30,6 +34,12 @@ pub struct GameState {
     backgound_animation: BackgroundAnimation,
     swaps_left_animation: SwapsLeftAnimation,
     animation_time: f32,
+    replay: Replay,
+    hint: Option<ShownHint>,
+    // Only set while the player wants to be warned when the puzzle can no longer be solved.
+    stuck_check: Option<StuckCheck>,
+    warn_when_stuck: bool,
+    show_check: bool,
 }
 
 impl GameState {
@@ -41,8 +51,155 @@ impl GameState {
             backgound_animation: BackgroundAnimation::new(puzzle.grid()),
             swaps_left_animation: SwapsLeftAnimation::new(puzzle.swaps_made()),
             animation_time: 0.0,
+            replay: Replay::new(puzzle),
+            hint: None,
+            stuck_check: None,
+            warn_when_stuck: false,
+            show_check: false,
         }
     }
+
+    /// Whether the controls include a Check button, which marks the tiles that break the
+    /// rules.
+    pub fn set_show_check(&mut self, show: bool) {
+        self.show_check = show;
+    }
+
+    /// Whether to check in the background, after each move, that a solution still fits in
+    /// the swaps left.
+    pub fn set_warn_when_stuck(&mut self, warn: bool) {
+        self.warn_when_stuck = warn;
+        if !warn {
+            self.stuck_check = None;
+        }
+    }
+
+    /// Everything played since this state was created, e.g. to share a finished attempt.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// The hint shown for the current position, if the player asked for one there.
+    fn current_hint(&self, puzzle: &PlayingPuzzle) -> Option<&ShownHint> {
+        self.hint
+            .as_ref()
+            .filter(|shown| shown.position == puzzle.moves().current())
+    }
+
+    /// Whether the current position is known to have no solution in the swaps left.
+    fn is_stuck(&self, puzzle: &PlayingPuzzle) -> bool {
+        self.stuck_check.as_ref().is_some_and(|check| {
+            check.position == puzzle.moves().current() && check.result == Some(Hint::NoSolution)
+        })
+    }
+
+    /// Keeps a dead-end check running for the current position.
+    fn update_stuck_check(&mut self, puzzle: &PlayingPuzzle) {
+        if !self.warn_when_stuck {
+            return;
+        }
+        // Puzzles are generated to be solvable from the start.
+        if puzzle.swaps_made() == 0 {
+            self.stuck_check = None;
+            return;
+        }
+        let position = puzzle.moves().current();
+        match &mut self.stuck_check {
+            Some(check) if check.position == position => check.poll(),
+            _ => self.stuck_check = Some(StuckCheck::start(puzzle)),
+        }
+    }
+}
+
+/// Looks for any solution from one position, to warn the player once there is none.
+struct StuckCheck {
+    position: Option<usize>,
+    task: Option<HintTask>,
+    result: Option<Hint>,
+}
+
+impl StuckCheck {
+    fn start(puzzle: &PlayingPuzzle) -> Self {
+        Self {
+            position: puzzle.moves().current(),
+            task: Some(HintTask::start(puzzle, Hint::LIMITS)),
+            result: None,
+        }
+    }
+
+    fn poll(&mut self) {
+        if let Some(result) = self.task.as_mut().and_then(HintTask::poll) {
+            self.task = None;
+            self.result = Some(result);
+        }
+    }
+}
+
+/// A hint for one position in the puzzle. It goes stale once the player moves on.
+struct ShownHint {
+    position: Option<usize>,
+    // Runs until the hint is found.
+    task: Option<HintTask>,
+    hint: Option<Hint>,
+    // A tile of the first swap the puzzle was generated with, mentioned if the search
+    // gives up.
+    start_tile: GridIndex,
+    // How many tiles of a suggested swap are highlighted; asking again shows both.
+    tiles: usize,
+}
+
+impl ShownHint {
+    fn start(puzzle: &PlayingPuzzle) -> Self {
+        Self {
+            position: puzzle.moves().current(),
+            task: Some(HintTask::start(puzzle, Hint::LIMITS)),
+            hint: None,
+            start_tile: puzzle.puzzle().hint(),
+            tiles: 1,
+        }
+    }
+
+    fn poll(&mut self) {
+        if let Some(hint) = self.task.as_mut().and_then(HintTask::poll) {
+            self.task = None;
+            self.hint = Some(hint);
+        }
+    }
+
+    fn tiles(&self) -> Vec<GridIndex> {
+        match self.hint {
+            Some(Hint::Swap(record)) => [record.a(), record.b()]
+                .into_iter()
+                .take(self.tiles)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn can_escalate(&self) -> bool {
+        matches!(self.hint, Some(Hint::Swap(_))) && self.tiles < 2
+    }
+
+    fn description(&self) -> String {
+        match self.hint {
+            None => "Looking for a hint...".to_owned(),
+            Some(Hint::Swap(record)) if self.tiles < 2 => {
+                format!("Move the tile on {}", square_name(record.a()))
+            }
+            Some(Hint::Swap(record)) => format!("Swap {record}"),
+            Some(Hint::Solved) => "The puzzle is already solved".to_owned(),
+            Some(Hint::NoSolution) => "No solution fits in the swaps left. Try undoing.".to_owned(),
+            Some(Hint::GaveUp(_)) => format!(
+                "Could not find a hint in time. From the start, move the tile on {}.",
+                square_name(self.start_tile)
+            ),
+        }
+    }
+
+    /// Whether the hint is shown as a message rather than on the board.
+    fn has_message(&self) -> bool {
+        !matches!(self.hint, Some(Hint::Swap(_) | Hint::Solved))
+    }
 }
 
 pub struct GameInputState {
@@ -78,7 +235,6 @@ impl GameInputState {
 
 #[derive(Debug, Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
 pub struct PuzzleState {
-    pub hint_shown: bool,
     pub solved: bool,
 }
 
@@ -172,48 +328,67 @@ fn update_input(
 
 struct ControlsResponse {
     undo: bool,
+    redo: bool,
     reset: bool,
     hint: bool,
+    check: bool,
     skip: bool,
 }
 
-fn control_button(
+pub(super) fn control_button(
     ui: &mut Ui,
     palette: &Palette,
     name: impl Into<String>,
     disabled: bool,
     highlight: bool,
-) -> bool {
+) -> Response {
     let mut button = Button::new(egui::RichText::new(name).text_style(egui::TextStyle::Heading));
     if highlight {
         button = button.fill(palette.get(Color::SWAP).linear_multiply(0.33));
     }
     ui.vertical_centered_justified(move |ui| ui.add_enabled(!disabled, button))
         .inner
-        .clicked()
 }
 
 fn draw_controls(
     ui: &mut Ui,
     palette: &Palette,
-    show_hint: bool,
+    hint: Option<&ShownHint>,
     solved: bool,
+    show_check: bool,
 ) -> ControlsResponse {
-    ui.columns(4, |columns| {
-        let undo = control_button(&mut columns[0], palette, "Undo", false, false);
-        let reset = control_button(&mut columns[1], palette, "Reset", false, false);
-        let hint = control_button(&mut columns[2], palette, "Hint", show_hint, false);
+    ui.columns(if show_check { 5 } else { 4 }, |columns| {
+        let suggest_undo = hint.is_some_and(|shown| shown.hint == Some(Hint::NoSolution));
+        let undo = control_button(&mut columns[0], palette, "Undo", false, suggest_undo).clicked();
+        let reset = control_button(&mut columns[1], palette, "Reset", false, false).clicked();
+        let hint_disabled = hint.is_some_and(|shown| !shown.can_escalate());
+        let mut hint_button =
+            control_button(&mut columns[2], palette, "Hint", hint_disabled, false);
+        if let Some(shown) = hint {
+            let description = shown.description();
+            hint_button = hint_button
+                .on_hover_text(&description)
+                .on_disabled_hover_text(description);
+        }
+        let hint = hint_button.clicked();
+        let check = show_check
+            && control_button(&mut columns[3], palette, "Check", false, false)
+                .on_hover_text("Mark the tiles that break the rules")
+                .clicked();
         let skip = control_button(
-            &mut columns[3],
+            columns.last_mut().expect("there are columns"),
             palette,
             if solved { "Next" } else { "Skip" },
             false,
             solved,
-        );
+        )
+        .clicked();
         ControlsResponse {
             undo,
+            redo: false,
             reset,
             hint,
+            check,
             skip,
         }
     })
@@ -228,10 +403,94 @@ fn handle_events(ui: &Ui, controls: &mut ControlsResponse) {
         pressed: true,
         modifiers,
         ..
+    } if modifiers.matches_logically(Modifiers::COMMAND) && !modifiers.shift)
+        });
+    controls.redo = controls.redo
+        || events.iter().any(|e| {
+            matches!(e, egui::Event::Key {
+        key: egui::Key::Z,
+        pressed: true,
+        modifiers,
+        ..
+    } if modifiers.matches_logically(Modifiers::COMMAND | Modifiers::SHIFT))
+                || matches!(e, egui::Event::Key {
+        key: egui::Key::Y,
+        pressed: true,
+        modifiers,
+        ..
     } if modifiers.matches_logically(Modifiers::COMMAND))
         });
 }
 
+/// Brings the simulation up to date after the puzzle's grid changed outside a regular swap.
+pub(super) fn sync_simulation(
+    applied: impl IntoIterator<Item = SwapRecord>,
+    puzzle: &PlayingPuzzle,
+    state: &mut GameState,
+    puzzle_state: &mut PuzzleState,
+) {
+    for record in applied {
+        state.simulation.swap(record);
+    }
+    state.simulation.update_fill(puzzle.grid());
+    state.input.clear();
+    state.solved = puzzle.is_solved();
+    puzzle_state.solved = puzzle_state.solved || state.solved == PuzzleSolveState::Solved;
+}
+
+/// Moves the puzzle to a move in its history, or the start for `None`, e.g. from the
+/// history panel.
+pub fn jump_to_move(
+    target: Option<usize>,
+    puzzle: &mut PlayingPuzzle,
+    state: &mut GameState,
+    puzzle_state: &mut PuzzleState,
+) {
+    let (undone, _) = puzzle.moves().path_to(target);
+    let applied = puzzle.jump_to(target);
+    for (i, record) in applied.iter().enumerate() {
+        let action = if i < undone.len() {
+            ReplayAction::Undo
+        } else {
+            ReplayAction::Redo(*record)
+        };
+        state.replay.record(state.animation_time, action);
+    }
+    if !applied.is_empty() {
+        sync_simulation(applied, puzzle, state, puzzle_state);
+    }
+}
+
+/// One marker per tile, for the most serious problem on it.
+fn check_markers(report: &SolveReport) -> Vec<(GridIndex, CheckMarker)> {
+    let conflicts = report
+        .double_filled
+        .iter()
+        .map(|index| (index.grid_index(), CheckMarker::Conflict));
+    let splits = report.split_regions.iter().flat_map(|region| {
+        region
+            .layers
+            .iter()
+            .map(|index| (index.grid_index(), CheckMarker::Split(region.color)))
+    });
+    let open_ends = report
+        .open_ends
+        .iter()
+        .map(|index| (index.grid_index(), CheckMarker::OpenEnd));
+    let unfilled = report
+        .unfilled
+        .iter()
+        .map(|index| (index.grid_index(), CheckMarker::Unfilled));
+
+    let mut markers: Vec<(GridIndex, CheckMarker)> = Vec::new();
+    for (index, marker) in conflicts.chain(splits).chain(open_ends).chain(unfilled) {
+        if !markers.iter().any(|(marked, _)| *marked == index) {
+            markers.push((index, marker));
+        }
+    }
+    markers
+}
+
 fn handle_controls(
     controls: ControlsResponse,
     puzzle: &mut PlayingPuzzle,
@@ -240,20 +499,36 @@ fn handle_controls(
 ) -> Option<GameCompletionAction> {
     if controls.undo {
         if let Some(record) = puzzle.try_undo() {
-            state.simulation.swap(SwapRecord {
-                a: record.a,
-                b: record.b,
-                a_rotation: record.b_rotation.inverse(),
-                b_rotation: record.a_rotation.inverse(),
-            });
-            state.simulation.update_fill(puzzle.grid());
-            state.input.clear();
-            state.solved = puzzle.is_solved();
-            puzzle_state.solved = puzzle_state.solved || state.solved == PuzzleSolveState::Solved;
+            state
+                .replay
+                .record(state.animation_time, ReplayAction::Undo);
+            sync_simulation([record.inverse()], puzzle, state, puzzle_state);
+        }
+    }
+    if controls.redo {
+        if let Some(record) = puzzle.try_redo() {
+            state
+                .replay
+                .record(state.animation_time, ReplayAction::Redo(record));
+            sync_simulation([record], puzzle, state, puzzle_state);
         }
     }
     if controls.hint {
-        puzzle_state.hint_shown = true;
+        let position = puzzle.moves().current();
+        match state
+            .hint
+            .as_mut()
+            .filter(|shown| shown.position == position)
+        {
+            Some(shown) if shown.can_escalate() => shown.tiles += 1,
+            Some(_) => {}
+            None => state.hint = Some(ShownHint::start(puzzle)),
+        }
+    }
+    if controls.check {
+        state
+            .backgound_animation
+            .show_check(check_markers(&puzzle.solve_report()));
     }
 
     if controls.reset {
@@ -278,14 +553,52 @@ pub fn update_game(
     mesh_data: &SegmentMeshData,
 ) -> Option<GameCompletionAction> {
     ui.ctx().request_repaint();
+    let (response, game_sizing) = layout_game(ui, puzzle.size(), style);
+    update_game_after_sizing(
+        ui,
+        puzzle,
+        state,
+        puzzle_state,
+        response,
+        game_sizing,
+        mesh_data,
+    )
+}
+
+pub(super) struct GameSizing {
+    painter: Painter,
+    cell_size: f32,
+    game_rect: Rect,
+    pub(super) controls_rect: Rect,
+    indicators_rect: Rect,
+}
 
+impl GameSizing {
+    fn to_screen(&self, bounds: GridSize) -> RectTransform {
+        let game_coords = Rect::from_min_size(
+            Pos2::new(-0.5, -0.5),
+            Vec2 {
+                x: bounds.width as f32,
+                y: bounds.height as f32,
+            },
+        );
+        emath::RectTransform::from_to(game_coords, self.game_rect)
+    }
+}
+
+/// Allocates the whole available space and splits it into the controls, the grid and the
+/// indicators below it.
+pub(super) fn layout_game(
+    ui: &mut Ui,
+    bounds: GridSize,
+    style: &GameStyle,
+) -> (Response, GameSizing) {
     const CONTROLS_HEIGHT: f32 = 50.0;
     const INDICATORS_HEIGHT: f32 = 50.0;
     const MAX_SIZE: f32 = 72.0;
 
     let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
 
-    let bounds = puzzle.size();
     let margin: egui::Margin = ui.style().spacing.window_margin;
     let max_size: Vec2 = painter.clip_rect().size();
     let game_width_max = max_size.x - margin.sum().x;
@@ -327,11 +640,7 @@ pub fn update_game(
         indicators_size,
     );
 
-    update_game_after_sizing(
-        ui,
-        puzzle,
-        state,
-        puzzle_state,
+    (
         response,
         GameSizing {
             painter,
@@ -340,16 +649,15 @@ pub fn update_game(
             controls_rect,
             indicators_rect,
         },
-        mesh_data,
     )
 }
 
-struct GameSizing {
-    painter: Painter,
-    cell_size: f32,
-    game_rect: Rect,
-    controls_rect: Rect,
-    indicators_rect: Rect,
+pub(super) fn current_palette(ui: &Ui) -> &'static Palette {
+    if ui.ctx().style().visuals.dark_mode {
+        &palette::DARK
+    } else {
+        &palette::DEFAULT
+    }
 }
 
 fn update_game_after_sizing(
@@ -361,39 +669,17 @@ fn update_game_after_sizing(
     game_sizing: GameSizing,
     mesh_data: &SegmentMeshData,
 ) -> Option<GameCompletionAction> {
-    let dt = ui.input(|i| i.stable_dt);
-    let palette = if ui.ctx().style().visuals.dark_mode {
-        &palette::DARK
-    } else {
-        &palette::DEFAULT
-    };
-
-    let GameSizing {
-        painter,
-        cell_size,
-        game_rect,
-        controls_rect,
-        indicators_rect,
-    } = game_sizing;
-    let bounds = puzzle.size();
-
-    let game_coords = Rect::from_min_size(
-        Pos2::new(-0.5, -0.5),
-        Vec2 {
-            x: bounds.width as f32,
-            y: bounds.height as f32,
-        },
-    );
-    let to_screen = emath::RectTransform::from_to(game_coords, game_rect);
-    let to_game_coords = to_screen.inverse();
+    let palette = current_palette(ui);
+    let to_game_coords = game_sizing.to_screen(puzzle.size()).inverse();
 
     let mut controls_response = ui
-        .allocate_ui_at_rect(controls_rect, |ui| {
+        .allocate_ui_at_rect(game_sizing.controls_rect, |ui| {
             draw_controls(
                 ui,
                 palette,
-                puzzle_state.hint_shown || puzzle.swaps_made() > 0,
+                state.current_hint(puzzle),
                 puzzle_state.solved,
+                state.show_check,
             )
         })
         .inner;
@@ -437,6 +723,9 @@ fn update_game_after_sizing(
 
     if let Some((a, b)) = swap_action {
         if let Some(record) = puzzle.try_swap(a, b) {
+            state
+                .replay
+                .record(state.animation_time, ReplayAction::Swap(record));
             state.simulation.swap(record);
             state.simulation.update_fill(puzzle.grid());
             state.solved = puzzle.is_solved();
@@ -444,6 +733,54 @@ fn update_game_after_sizing(
         }
     }
 
+    // A stale hint is dropped, which also stops its search.
+    if state.current_hint(puzzle).is_none() {
+        state.hint = None;
+    }
+    if let Some(shown) = &mut state.hint {
+        shown.poll();
+    }
+    state.update_stuck_check(puzzle);
+    let hint = state.hint.as_ref();
+    let hint_tiles = hint.map(ShownHint::tiles).unwrap_or_default();
+    let hint_message = hint
+        .filter(|shown| shown.has_message())
+        .map(ShownHint::description);
+    draw_game(ui, puzzle, state, &hint_tiles, &game_sizing, mesh_data);
+    if let Some(message) = hint_message {
+        game_sizing.painter.text(
+            game_sizing.indicators_rect.center_bottom(),
+            egui::Align2::CENTER_TOP,
+            message,
+            egui::FontId::proportional(16.0),
+            ui.visuals().text_color(),
+        );
+    }
+
+    completion_response
+}
+
+/// Steps the animations and draws the indicators and the grid, with a glow on the `hint`
+/// tiles.
+pub(super) fn draw_game(
+    ui: &Ui,
+    puzzle: &PlayingPuzzle,
+    state: &mut GameState,
+    hint: &[GridIndex],
+    game_sizing: &GameSizing,
+    mesh_data: &SegmentMeshData,
+) {
+    let dt = ui.input(|i| i.stable_dt);
+    let palette = current_palette(ui);
+    let GameSizing {
+        painter,
+        cell_size,
+        indicators_rect,
+        ..
+    } = game_sizing;
+    let cell_size = *cell_size;
+    let to_screen = game_sizing.to_screen(puzzle.size());
+
     state.animation_time += dt;
     state.simulation.step(dt, palette);
     if state.solved == PuzzleSolveState::Solved {
@@ -451,12 +788,13 @@ fn update_game_after_sizing(
     }
 
     state.swaps_left_animation.draw(
-        &painter,
+        painter,
         puzzle.swaps_made(),
         puzzle.swap_limit(),
+        state.is_stuck(puzzle),
         dt,
         SwapsLeftDrawData {
-            rect: indicators_rect,
+            rect: *indicators_rect,
             palette,
         },
     );
@@ -467,11 +805,8 @@ fn update_game_after_sizing(
                 x: grid_pos.x as f32,
                 y: grid_pos.y as f32,
             };
-        let show_hint = puzzle_state.hint_shown
-            && puzzle.puzzle().hint() == grid_pos
-            && puzzle.swaps_made() == 0;
         state.backgound_animation.draw_background_cell(
-            &painter,
+            painter,
             palette,
             cell,
             dt,
@@ -479,7 +814,7 @@ fn update_game_after_sizing(
                 index: grid_pos,
                 center,
                 scale: cell_size,
-                show_hint,
+                show_hint: hint.contains(&grid_pos),
             },
         );
     }
@@ -502,7 +837,7 @@ fn update_game_after_sizing(
             };
         draw_cell(
             cell,
-            &painter,
+            painter,
             CellDrawData {
                 index: grid_pos,
                 center,
@@ -514,6 +849,4 @@ fn update_game_after_sizing(
             },
         );
     }
-
-    completion_response
 }
@@ -0,0 +1,24 @@
+use egui::Context;
+
+/// Shows that a puzzle is being generated. Returns true if the player cancelled.
+pub fn generation_window(ctx: &Context, attempts: usize) -> bool {
+    egui::Window::new("Generating puzzle")
+        .resizable([false, false])
+        .constrain(true)
+        .collapsible(false)
+        .title_bar(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Generating…");
+                if attempts > 1 {
+                    ui.weak(format!("(attempt {attempts})"));
+                }
+            });
+            ui.vertical_centered(|ui| ui.button("Cancel").clicked())
+                .inner
+        })
+        .and_then(|response| response.inner)
+        .unwrap_or(false)
+}
@@ -0,0 +1,84 @@
+use egui::{Context, RichText, Ui};
+
+use crate::gameplay::{MoveTree, PlayingPuzzle};
+
+/// Shows every line of moves tried on the puzzle. Returns the move the player picked, or
+/// `Some(None)` for the start.
+pub fn history_window(
+    ctx: &Context,
+    puzzle: &PlayingPuzzle,
+    open: &mut bool,
+) -> Option<Option<usize>> {
+    egui::Window::new("History")
+        .resizable([false, true])
+        .constrain(true)
+        .collapsible(true)
+        .title_bar(true)
+        .enabled(true)
+        .open(open)
+        .show(ctx, |ui| draw_history_window(ui, puzzle))
+        .and_then(|response| response.inner)
+        .flatten()
+}
+
+/// Draws a line of moves starting at `first`, a child of `parent`. After each move, the
+/// other moves tried in its place are drawn indented, as their own lines.
+fn draw_line(
+    ui: &mut Ui,
+    moves: &MoveTree,
+    mut parent: Option<usize>,
+    first: usize,
+    mut number: usize,
+    mut siblings_drawn: bool,
+    jump: &mut Option<Option<usize>>,
+) {
+    let mut id = first;
+    loop {
+        let record = moves.get(id).expect("lines are within the tree").record;
+        let mut text = RichText::new(format!("{number}. {}", record));
+        if !moves.is_applied(id) {
+            text = text.weak();
+        }
+        if ui
+            .selectable_label(moves.current() == Some(id), text)
+            .clicked()
+        {
+            *jump = Some(Some(id));
+        }
+
+        if !siblings_drawn {
+            for branch in moves.children(parent).filter(|child| *child != id) {
+                ui.indent(branch, |ui| {
+                    draw_line(ui, moves, parent, branch, number, true, jump)
+                });
+            }
+        }
+        siblings_drawn = false;
+
+        match moves.line_child(Some(id)) {
+            Some(next) => {
+                parent = Some(id);
+                id = next;
+                number += 1;
+            }
+            None => break,
+        }
+    }
+}
+
+fn draw_history_window(ui: &mut Ui, puzzle: &PlayingPuzzle) -> Option<Option<usize>> {
+    let moves = puzzle.moves();
+    let mut jump = None;
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        if ui
+            .selectable_label(moves.current().is_none(), "Start")
+            .clicked()
+        {
+            jump = Some(None);
+        }
+        if let Some(first) = moves.line_child(None) {
+            draw_line(ui, moves, None, first, 1, false, &mut jump);
+        }
+    });
+    jump
+}
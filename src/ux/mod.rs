@@ -2,15 +2,31 @@ mod background;
 mod bezier;
 mod cell;
 mod game;
+mod generation_window;
+mod history_window;
 mod mesh_data;
 mod palette;
+mod puzzle_code_window;
+mod replay_view;
+mod replay_window;
+mod seed_window;
 mod settings_config;
 mod settings_editor;
 mod simulation;
+mod solution_window;
 mod swaps_left;
 mod tutorial;
-pub use game::{update_game, GameCompletionAction, GameState, GameStyle, PuzzleState};
+pub use game::{
+    jump_to_move, update_game, GameCompletionAction, GameState, GameStyle, PuzzleState,
+};
+pub use generation_window::generation_window;
+pub use history_window::history_window;
 pub use mesh_data::SegmentMeshData;
+pub use puzzle_code_window::puzzle_code_window;
+pub use replay_view::{update_replay, ReplayView, ReplayViewAction};
+pub use replay_window::replay_window;
+pub use seed_window::seed_window;
 pub use settings_config::SettingsConfig;
-pub use settings_editor::edit_generator_settings;
+pub use settings_editor::{edit_generator_settings, generation_error_window};
+pub use solution_window::solution_window;
 pub use tutorial::tutorial_window;
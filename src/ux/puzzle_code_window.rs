@@ -0,0 +1,60 @@
+use egui::{Context, Ui};
+
+use crate::gameplay::{Puzzle, PuzzleError};
+
+pub fn puzzle_code_window(
+    ctx: &Context,
+    code_input: &mut String,
+    error: &mut Option<String>,
+    open: &mut bool,
+) -> Option<Puzzle> {
+    let puzzle = egui::Window::new("Load puzzle code")
+        .resizable([true, false])
+        .constrain(true)
+        .collapsible(true)
+        .title_bar(true)
+        .enabled(true)
+        .open(open)
+        .show(ctx, |ui| draw_puzzle_code_window(ui, code_input, error))
+        .and_then(|response| response.inner)
+        .flatten();
+    if puzzle.is_some() {
+        *open = false;
+        code_input.clear();
+    }
+    puzzle
+}
+
+fn load_code(code: &str) -> Result<Puzzle, String> {
+    let puzzle = Puzzle::from_code(code).map_err(|err| format!("Invalid code: {err}"))?;
+    puzzle
+        .validate()
+        .map_err(|errors| format!("Invalid puzzle: {}", PuzzleError::describe_all(&errors)))?;
+    Ok(puzzle)
+}
+
+fn draw_puzzle_code_window(
+    ui: &mut Ui,
+    code_input: &mut String,
+    error: &mut Option<String>,
+) -> Option<Puzzle> {
+    ui.label("Paste a puzzle code to play it:");
+    let puzzle = ui
+        .horizontal(|ui| {
+            if ui.text_edit_singleline(code_input).changed() {
+                *error = None;
+            }
+            let load = ui
+                .add_enabled(!code_input.trim().is_empty(), egui::Button::new("Load"))
+                .clicked();
+            if !load {
+                return None;
+            }
+            load_code(code_input).map_err(|err| *error = Some(err)).ok()
+        })
+        .inner;
+    if let Some(err) = error {
+        ui.colored_label(ui.visuals().error_fg_color, err.as_str());
+    }
+    puzzle
+}
@@ -0,0 +1,169 @@
+use egui::Ui;
+
+use crate::gameplay::{Replay, ReplayPlayer};
+
+use super::{
+    game::{
+        control_button, current_palette, draw_game, layout_game, sync_simulation, GameState,
+        GameStyle, PuzzleState,
+    },
+    SegmentMeshData,
+};
+
+/// Plays a replay in the game view, in place of the puzzle being played.
+pub struct ReplayView {
+    player: ReplayPlayer,
+    state: GameState,
+    puzzle_state: PuzzleState,
+    playing: bool,
+    // Showing the solution to the puzzle being played, rather than a loaded replay.
+    solution: bool,
+}
+
+impl ReplayView {
+    /// Expects a replay that passed [`Replay::validate`].
+    pub fn new(replay: Replay) -> Self {
+        let player = ReplayPlayer::new(replay);
+        let state = GameState::new(player.puzzle());
+        Self {
+            player,
+            state,
+            puzzle_state: PuzzleState::default(),
+            playing: true,
+            solution: false,
+        }
+    }
+
+    /// Shows a solution to the current puzzle, after which the player can try the puzzle
+    /// again or move on.
+    pub fn solution(replay: Replay) -> Self {
+        Self {
+            solution: true,
+            ..Self::new(replay)
+        }
+    }
+
+    fn restart(&mut self) {
+        *self = Self {
+            solution: self.solution,
+            ..Self::new(self.player.replay().clone())
+        };
+    }
+}
+
+pub enum ReplayViewAction {
+    Close,
+    /// Play the puzzle again from the start.
+    TryAgain,
+    /// Move on to the next puzzle.
+    Next,
+}
+
+struct ReplayControlsResponse {
+    play: bool,
+    step: bool,
+    restart: bool,
+    action: Option<ReplayViewAction>,
+}
+
+/// Draws the replay and its controls. Returns what the player chose to do next, if they
+/// are done watching.
+pub fn update_replay(
+    ui: &mut Ui,
+    view: &mut ReplayView,
+    style: &GameStyle,
+    mesh_data: &SegmentMeshData,
+) -> Option<ReplayViewAction> {
+    ui.ctx().request_repaint();
+    let palette = current_palette(ui);
+    let (_, game_sizing) = layout_game(ui, view.player.puzzle().size(), style);
+
+    let finished = view.player.is_finished();
+    let controls = ui
+        .allocate_ui_at_rect(game_sizing.controls_rect, |ui| {
+            ui.columns(4, |columns| {
+                let play_name = if view.playing && !finished {
+                    "Pause"
+                } else {
+                    "Play"
+                };
+                let play =
+                    control_button(&mut columns[0], palette, play_name, finished, false).clicked();
+                let step =
+                    control_button(&mut columns[1], palette, "Step", finished, false).clicked();
+                if view.solution {
+                    let try_again =
+                        control_button(&mut columns[2], palette, "Try again", false, finished)
+                            .clicked();
+                    let next =
+                        control_button(&mut columns[3], palette, "Next", false, false).clicked();
+                    ReplayControlsResponse {
+                        play,
+                        step,
+                        restart: false,
+                        action: if try_again {
+                            Some(ReplayViewAction::TryAgain)
+                        } else if next {
+                            Some(ReplayViewAction::Next)
+                        } else {
+                            None
+                        },
+                    }
+                } else {
+                    let restart =
+                        control_button(&mut columns[2], palette, "Restart", false, false).clicked();
+                    let close = control_button(&mut columns[3], palette, "Close", false, finished)
+                        .clicked();
+                    ReplayControlsResponse {
+                        play,
+                        step,
+                        restart,
+                        action: close.then_some(ReplayViewAction::Close),
+                    }
+                }
+            })
+        })
+        .inner;
+
+    if controls.restart {
+        view.restart();
+    }
+    if controls.play {
+        view.playing = !view.playing;
+    }
+
+    let dt = ui.input(|i| i.stable_dt);
+    let played = if controls.step {
+        view.playing = false;
+        view.player
+            .step()
+            .map(|applied| applied.into_iter().collect())
+    } else if view.playing {
+        view.player.advance(dt)
+    } else {
+        Ok(Vec::new())
+    };
+    match played {
+        Ok(applied) if !applied.is_empty() => sync_simulation(
+            applied,
+            view.player.puzzle(),
+            &mut view.state,
+            &mut view.puzzle_state,
+        ),
+        Ok(_) => {}
+        Err(err) => {
+            log::warn!("Stopping replay: {err}");
+            view.playing = false;
+        }
+    }
+
+    draw_game(
+        ui,
+        view.player.puzzle(),
+        &mut view.state,
+        &[],
+        &game_sizing,
+        mesh_data,
+    );
+    controls.action
+}
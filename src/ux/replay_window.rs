@@ -0,0 +1,66 @@
+use egui::{Context, Ui};
+
+use crate::gameplay::Replay;
+
+pub fn replay_window(
+    ctx: &Context,
+    replay_input: &mut String,
+    error: &mut Option<String>,
+    open: &mut bool,
+) -> Option<Replay> {
+    let replay = egui::Window::new("Load replay")
+        .resizable([true, true])
+        .constrain(true)
+        .collapsible(true)
+        .title_bar(true)
+        .enabled(true)
+        .open(open)
+        .show(ctx, |ui| draw_replay_window(ui, replay_input, error))
+        .and_then(|response| response.inner)
+        .flatten();
+    if replay.is_some() {
+        *open = false;
+        replay_input.clear();
+    }
+    replay
+}
+
+fn load_replay(text: &str) -> Result<Replay, String> {
+    let replay: Replay = text
+        .parse()
+        .map_err(|err| format!("Invalid replay: {err}"))?;
+    replay
+        .validate()
+        .map_err(|err| format!("Invalid replay: {err}"))?;
+    Ok(replay)
+}
+
+fn draw_replay_window(
+    ui: &mut Ui,
+    replay_input: &mut String,
+    error: &mut Option<String>,
+) -> Option<Replay> {
+    ui.label("Paste a replay to watch it:");
+    egui::ScrollArea::vertical()
+        .max_height(200.0)
+        .show(ui, |ui| {
+            if ui
+                .add(egui::TextEdit::multiline(replay_input).code_editor())
+                .changed()
+            {
+                *error = None;
+            }
+        });
+    let load = ui
+        .add_enabled(!replay_input.trim().is_empty(), egui::Button::new("Watch"))
+        .clicked();
+    if let Some(err) = error {
+        ui.colored_label(ui.visuals().error_fg_color, err.as_str());
+    }
+    if !load {
+        return None;
+    }
+    load_replay(replay_input)
+        .map_err(|err| *error = Some(err))
+        .ok()
+}
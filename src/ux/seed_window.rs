@@ -0,0 +1,51 @@
+use egui::{Context, Ui};
+
+pub fn seed_window(
+    ctx: &Context,
+    current_seed: Option<u64>,
+    seed_input: &mut String,
+    open: &mut bool,
+) -> Option<u64> {
+    egui::Window::new("Puzzle seed")
+        .resizable([true, false])
+        .constrain(true)
+        .collapsible(true)
+        .title_bar(true)
+        .enabled(true)
+        .open(open)
+        .show(ctx, |ui| draw_seed_window(ui, current_seed, seed_input))
+        .and_then(|response| response.inner)
+        .flatten()
+}
+
+fn draw_seed_window(
+    ui: &mut Ui,
+    current_seed: Option<u64>,
+    seed_input: &mut String,
+) -> Option<u64> {
+    match current_seed {
+        Some(seed) => {
+            ui.horizontal(|ui| {
+                ui.label(format!("Current puzzle: {seed}"));
+                if ui.button("Copy").clicked() {
+                    ui.output_mut(|output| output.copied_text = seed.to_string());
+                }
+            });
+        }
+        None => {
+            ui.label("The current puzzle was not generated from a seed.");
+        }
+    }
+    ui.separator();
+
+    ui.label("Enter a seed to play its puzzle with the current generator settings:");
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(seed_input);
+        let parsed = seed_input.trim().parse::<u64>().ok();
+        let play = ui
+            .add_enabled(parsed.is_some(), egui::Button::new("Play"))
+            .clicked();
+        parsed.filter(|_| play)
+    })
+    .inner
+}
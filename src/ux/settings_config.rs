@@ -1,13 +1,22 @@
 use crate::{
-    generator::{GeneratorSettings, SourceSettings},
+    gameplay::Difficulty,
+    generator::{GeneratorRng, GeneratorSettings, SourceSettings},
     grids::GridSize,
 };
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct SettingsConfig {
     pub custom_override: bool,
     pub custom_settings: GeneratorSettings,
+    #[serde(default)]
+    pub target_difficulty: Option<Difficulty>,
+    /// Dim the swaps left once the puzzle can no longer be solved with them.
+    #[serde(default)]
+    pub warn_when_stuck: bool,
+    /// Show a Check button that marks the tiles breaking the rules.
+    #[serde(default)]
+    pub check_button: bool,
 }
 
 struct Odds<T> {
@@ -42,21 +51,42 @@ struct GridData {
     swap_count: u8,
 }
 
-fn get_random_grid_data<R>(rng: &mut R) -> GridData
+fn get_random_grid_data<R>(rng: &mut R, target: Option<Difficulty>) -> GridData
 where
     R: rand::Rng + ?Sized,
 {
+    // Weights for no target, then for easy, medium and hard puzzles.
+    const SIZE_WEIGHTS: [((GridSize, usize), [u32; 4]); 7] = [
+        ((GridSize::new(2, 3), 1), [3, 6, 3, 0]),
+        ((GridSize::new(1, 4), 0), [6, 8, 6, 0]),
+        ((GridSize::new(2, 4), 1), [9, 10, 9, 2]),
+        ((GridSize::new(3, 3), 2), [15, 8, 15, 8]),
+        ((GridSize::new(3, 4), 2), [8, 2, 8, 10]),
+        ((GridSize::new(4, 4), 3), [4, 0, 4, 8]),
+        ((GridSize::new(5, 5), 4), [2, 0, 2, 4]),
+    ];
+    const SWAP_WEIGHTS: [(i8, [u32; 4]); 4] = [
+        (-1, [10, 40, 10, 0]),
+        (0, [150, 150, 150, 100]),
+        (1, [30, 5, 30, 60]),
+        (2, [3, 0, 3, 15]),
+    ];
+    let column = match target {
+        None => 0,
+        Some(Difficulty::Easy) => 1,
+        Some(Difficulty::Medium) => 2,
+        Some(Difficulty::Hard) => 3,
+    };
+
     let mut size_odds = Odds::new();
-    size_odds.add((GridSize::new(2, 3), 1), 3);
-    size_odds.add((GridSize::new(1, 4), 0), 6);
-    size_odds.add((GridSize::new(2, 4), 1), 9);
-    size_odds.add((GridSize::new(3, 3), 2), 15);
-    size_odds.add((GridSize::new(3, 4), 2), 8);
-    size_odds.add((GridSize::new(4, 4), 3), 4);
-    size_odds.add((GridSize::new(5, 5), 4), 2);
+    for (entry, weights) in SIZE_WEIGHTS {
+        if weights[column] > 0 {
+            size_odds.add(entry, weights[column]);
+        }
+    }
     let (size, missing) = *size_odds.get(rng).unwrap();
     let area = size.width * size.height;
-    let par_swaps = match area {
+    let par_swaps: u8 = match area {
         0..=5 => 2,
         6..=7 => 3,
         8..=9 => 4,
@@ -64,10 +94,11 @@ where
     };
 
     let mut swap_odds: Odds<u8> = Odds::new();
-    swap_odds.add(par_swaps - 1, 10);
-    swap_odds.add(par_swaps, 150);
-    swap_odds.add(par_swaps + 1, 30);
-    swap_odds.add(par_swaps + 2, 3);
+    for (offset, weights) in SWAP_WEIGHTS {
+        if weights[column] > 0 {
+            swap_odds.add(par_swaps.saturating_add_signed(offset), weights[column]);
+        }
+    }
     let swap_count = *swap_odds.get(rng).unwrap();
 
     GridData {
@@ -78,22 +109,42 @@ where
 }
 
 impl SettingsConfig {
+    /// Whether both configs generate puzzles the same way, ignoring the options that only
+    /// change how a puzzle is played.
+    pub fn generates_like(&self, other: &Self) -> bool {
+        self.custom_override == other.custom_override
+            && self.custom_settings == other.custom_settings
+            && self.target_difficulty == other.target_difficulty
+    }
+
     pub fn get_current_settings(&self) -> GeneratorSettings {
-        if self.custom_override {
+        self.get_settings_for_seed(rand::random())
+    }
+
+    /// Settings that generate the same puzzle every time for a given seed.
+    pub fn get_settings_for_seed(&self, seed: u64) -> GeneratorSettings {
+        let mut settings = if self.custom_override {
             self.custom_settings.clone()
         } else {
-            self.get_random_settings()
-        }
+            Self::get_random_settings(
+                &mut GeneratorRng::seed_from_u64(seed),
+                self.target_difficulty,
+            )
+        };
+        settings.seed = Some(seed);
+        settings.target_difficulty = self.target_difficulty;
+        settings
     }
 
-    fn get_random_settings(&self) -> GeneratorSettings {
-        let rng = &mut rand::thread_rng();
-
+    fn get_random_settings(
+        rng: &mut GeneratorRng,
+        target: Option<Difficulty>,
+    ) -> GeneratorSettings {
         let GridData {
             size,
             missing,
             swap_count,
-        } = get_random_grid_data(rng);
+        } = get_random_grid_data(rng, target);
 
         GeneratorSettings {
             size,
@@ -110,12 +161,16 @@ impl SettingsConfig {
             extra_source_chance: rng.gen_range(0.075..0.2),
 
             intersection_chance: rng.gen_range(0.0..0.8),
-            max_intersections: rng.gen_range(0..(size.width * size.height * 2 / 3)),
+            max_intersections: rng.gen_range(0..(size.width * size.height * 2 / 3) as u32) as usize,
 
             knockout_loop_chance: 0.99 - rng.gen_range(0.0f32..0.5).powi(2),
 
-            check_solution_len: (swap_count - 1).min(3) as usize,
+            check_solution_len: (swap_count - 1).min(4) as usize,
             check_solution_retries: 3,
+            max_solutions: 0,
+            target_difficulty: None,
+
+            seed: None,
         }
     }
 }
@@ -1,11 +1,15 @@
 use egui::{Context, Slider, Ui};
 
-use crate::generator::{GeneratorSettings, SourceSettings};
+use crate::{
+    gameplay::Difficulty,
+    generator::{GenerationError, GeneratorSettings, SourceSettings},
+};
 
 pub fn edit_generator_settings(
     ctx: &Context,
     use_settings: &mut bool,
     settings: &mut GeneratorSettings,
+    target_difficulty: &mut Option<Difficulty>,
     open: &mut bool,
 ) {
     egui::Window::new("Generator settings")
@@ -17,6 +21,14 @@ pub fn edit_generator_settings(
         .enabled(true)
         .open(open)
         .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Difficulty");
+                ui.radio_value(target_difficulty, None, "Any");
+                for difficulty in Difficulty::ALL {
+                    ui.radio_value(target_difficulty, Some(difficulty), difficulty.name());
+                }
+            });
+            ui.separator();
             ui.checkbox(use_settings, "Use custom generator");
             ui.separator();
             ui.add_enabled_ui(*use_settings, |ui| {
@@ -35,6 +47,26 @@ pub fn edit_generator_settings(
         });
 }
 
+pub fn generation_error_window(ctx: &Context, error: &mut Option<GenerationError>) {
+    let mut open = error.is_some();
+    if let Some(err) = error {
+        egui::Window::new("Could not generate a puzzle")
+            .resizable([true, false])
+            .constrain(true)
+            .collapsible(false)
+            .title_bar(true)
+            .enabled(true)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(err.to_string());
+                ui.label("Try adjusting the generator settings.");
+            });
+    }
+    if !open {
+        *error = None;
+    }
+}
+
 fn edit_generator_settings_grid(ui: &mut Ui, settings: &mut GeneratorSettings) {
     ui.label("Swap count");
     ui.add(Slider::new(&mut settings.swap_count, 1..=6));
@@ -132,4 +164,9 @@ fn edit_advanced_settings_grid(ui: &mut Ui, settings: &mut GeneratorSettings) {
         .on_hover_text("If a shorter solution is found, how many times to retry before giving up.");
     ui.add(Slider::new(&mut settings.check_solution_retries, 1..=5));
     ui.end_row();
+
+    ui.label("Maximum solutions")
+        .on_hover_text("Reject puzzles with more solutions than this. Zero allows any number.");
+    ui.add(Slider::new(&mut settings.max_solutions, 0..=10));
+    ui.end_row();
 }
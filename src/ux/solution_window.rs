@@ -0,0 +1,63 @@
+use egui::Context;
+
+use crate::{gameplay::SwapRecord, generator::SolveTask};
+
+/// Shows that a solution is being searched for, and why it could not be found. Returns the
+/// solution once it is found.
+pub fn solution_window(
+    ctx: &Context,
+    search: &mut Option<SolveTask>,
+    error: &mut Option<String>,
+) -> Option<Vec<SwapRecord>> {
+    let mut solution = None;
+    if let Some(task) = search {
+        match task.poll() {
+            Some(Ok(Some(found))) => solution = Some(found),
+            Some(Ok(None)) => {
+                *error = Some("There is no solution within the swap limit.".to_owned())
+            }
+            Some(Err(err)) => *error = Some(format!("The search stopped early: {err}.")),
+            None => {
+                ctx.request_repaint();
+                let cancelled = egui::Window::new("Finding a solution")
+                    .resizable([false, false])
+                    .constrain(true)
+                    .collapsible(false)
+                    .title_bar(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Finding a solution…");
+                        });
+                        ui.vertical_centered(|ui| ui.button("Cancel").clicked())
+                            .inner
+                    })
+                    .and_then(|response| response.inner)
+                    .unwrap_or(false);
+                if !cancelled {
+                    return None;
+                }
+            }
+        }
+        *search = None;
+    }
+
+    let mut open = error.is_some();
+    if let Some(err) = error {
+        egui::Window::new("Could not show the solution")
+            .resizable([true, false])
+            .constrain(true)
+            .collapsible(false)
+            .title_bar(true)
+            .enabled(true)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(err.as_str());
+            });
+    }
+    if !open {
+        *error = None;
+    }
+    solution
+}
@@ -8,6 +8,8 @@ use super::palette::Palette;
 
 pub struct SwapsLeftAnimation {
     swaps_used: f32,
+    // Fades in while the puzzle can no longer be solved in the swaps left.
+    stuck: f32,
 }
 
 pub struct SwapsLeftDrawData<'a> {
@@ -51,13 +53,21 @@ fn draw_swap_indicator_with_color(
     );
 }
 
-fn draw_swap_indicator(painter: &Painter, center: Pos2, t: f32, data: &SwapsLeftDrawData<'_>) {
+fn draw_swap_indicator(
+    painter: &Painter,
+    center: Pos2,
+    t: f32,
+    stuck: f32,
+    data: &SwapsLeftDrawData<'_>,
+) {
     draw_swap_indicator_with_color(painter, center, t, data.palette.empty, data);
     draw_swap_indicator_with_color(
         painter,
         center,
         t,
-        data.palette.get(Color::Purple).gamma_multiply(1.0 - t),
+        data.palette
+            .get(Color::Purple)
+            .gamma_multiply((1.0 - t) * (1.0 - 0.7 * stuck)),
         data,
     );
 }
@@ -66,6 +76,7 @@ impl SwapsLeftAnimation {
     pub fn new(swaps_made: usize) -> Self {
         Self {
             swaps_used: swaps_made as f32,
+            stuck: 0.0,
         }
     }
 
@@ -74,11 +85,13 @@ impl SwapsLeftAnimation {
         painter: &Painter,
         swaps_made: usize,
         swap_limit: usize,
+        stuck: bool,
         dt: f32,
         data: SwapsLeftDrawData<'_>,
     ) {
         const SPEED: f32 = 1.0;
         const SPEED_UNDO: f32 = 3.0;
+        const SPEED_STUCK: f32 = 0.5;
 
         let target = swaps_made as f32;
         if self.swaps_used < target {
@@ -86,6 +99,11 @@ impl SwapsLeftAnimation {
         } else {
             self.swaps_used = (self.swaps_used - dt * SPEED_UNDO).max(target);
         }
+        self.stuck = if stuck {
+            (self.stuck + dt * SPEED_STUCK).min(1.0)
+        } else {
+            (self.stuck - dt * SPEED_UNDO).max(0.0)
+        };
 
         for i in 0..swap_limit {
             let i_float = i as f32;
@@ -103,7 +121,7 @@ impl SwapsLeftAnimation {
                 data.rect.center().y,
             );
 
-            draw_swap_indicator(painter, center, t, &data);
+            draw_swap_indicator(painter, center, t, self.stuck, &data);
         }
     }
 }